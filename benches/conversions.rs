@@ -0,0 +1,43 @@
+use buffer_the_gap::Buffer;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// about 10 MB of mixed ascii and multi-byte text
+fn large_buffer() -> Buffer {
+    let line = "hello Θ world ƽ, the quick brown fox jumps over the lazy dog\n";
+    let mut buffer = Buffer::from(line.repeat(10_000_000 / line.len()).as_str());
+    // put the gap in the middle so lookups land on both sides of it
+    buffer.insert_str(buffer.len_chars() / 2, "edit");
+    buffer
+}
+
+// deterministic positions so runs are comparable
+fn positions(len: usize, count: usize) -> Vec<usize> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..count)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % (len as u64 + 1)) as usize
+        })
+        .collect()
+}
+
+fn char_to_byte(c: &mut Criterion) {
+    let buffer = large_buffer();
+    let positions = positions(buffer.len_chars(), 100);
+    let mut group = c.benchmark_group("conversions");
+    // each lookup scans the text, so keep the sample count small
+    group.sample_size(10);
+    group.bench_function("char_to_byte", |b| {
+        b.iter(|| {
+            for &pos in &positions {
+                black_box(buffer.char_to_byte(pos));
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, char_to_byte);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use buffer_the_gap::Buffer;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+const TEXT_LEN: usize = 2000;
+
+// About 2 MB of multi-byte text with the cursor in the middle, so anything
+// that recalculates the cursor has to scan half of it. The gap is at the end
+// and already large enough, so the benches do not measure reallocation.
+fn large_buffer() -> Buffer {
+    let mut buffer = Buffer::from("Θ".repeat(1_000_000).as_str());
+    buffer.move_gap(buffer.len_chars());
+    buffer.reserve_gap(TEXT_LEN * 2);
+    buffer.set_cursor(buffer.len_chars() / 2);
+    buffer
+}
+
+fn append(c: &mut Criterion) {
+    let buffer = large_buffer();
+    let text = "ƽ".repeat(TEXT_LEN / 2);
+    let mut group = c.benchmark_group("append");
+    group.bench_function("push_str", |b| {
+        b.iter_batched_ref(
+            || buffer.clone(),
+            |buffer| buffer.push_str(black_box(&text)),
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("insert_str", |b| {
+        b.iter_batched_ref(
+            || buffer.clone(),
+            |buffer| buffer.insert_str(buffer.len_chars(), black_box(&text)),
+            BatchSize::LargeInput,
+        );
+    });
+    // what push_str has to do if it goes through insert_str
+    group.bench_function("insert_str_keep_cursor", |b| {
+        b.iter_batched_ref(
+            || buffer.clone(),
+            |buffer| {
+                let cursor = buffer.len_chars() / 2;
+                buffer.insert_str(buffer.len_chars(), black_box(&text));
+                buffer.set_cursor(cursor);
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, append);
+criterion_main!(benches);
@@ -11,13 +11,13 @@ fuzz_target!(|transactions: Vec<Result<(usize, &str), (usize, usize)>>| {
             Ok((pos, text)) => {
                 let pos = pos % (buffer.len() + 2);
                 buffer.set_cursor(pos);
-                buffer.insert(text)
+                buffer.insert(text);
             }
             // Delete
             Err((start, end)) => {
                 let start = start % (buffer.len() + 2);
                 let end = end % (buffer.len() + 2);
-                buffer.delete_region(start, end)
+                buffer.delete_region(start, end);
             }
         }
     }
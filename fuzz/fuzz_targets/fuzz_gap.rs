@@ -0,0 +1,40 @@
+#![no_main]
+
+use gap_buffer::Buffer;
+use libfuzzer_sys::fuzz_target;
+
+fn byte_pos(string: &str, pos: usize) -> usize {
+    string
+        .char_indices()
+        .nth(pos)
+        .map_or(string.len(), |(i, _)| i)
+}
+
+// Move the gap around between edits and check that no gap bytes leak into
+// the text and that the counts never include the gap.
+fuzz_target!(|input: (&str, Vec<(usize, Result<&str, usize>)>)| {
+    let (init, edits) = input;
+    let mut buffer = Buffer::from(init);
+    let mut string = String::from(init);
+    for (gap, edit) in edits {
+        buffer.move_gap(gap % (buffer.len_chars() + 1));
+        let pos = buffer.byte_to_char(buffer.gap().0);
+        match edit {
+            // Insert at the gap
+            Ok(text) => {
+                buffer.insert_str(pos, text);
+                string.insert_str(byte_pos(&string, pos), text);
+            }
+            // Delete after the gap
+            Err(size) => {
+                let end = (pos + size % 8).min(buffer.len_chars());
+                buffer.delete_region(pos, end);
+                string.replace_range(byte_pos(&string, pos)..byte_pos(&string, end), "");
+            }
+        }
+        assert_eq!(buffer.to_string(), string);
+        assert_eq!(buffer.len(), string.len());
+        assert_eq!(buffer.len_chars(), string.chars().count());
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+});
@@ -2,21 +2,24 @@
 #![allow(clippy::uninlined_format_args)]
 #![allow(clippy::must_use_candidate)]
 use std::{
+    borrow::Cow,
     fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    io::{self, Read, Write},
     ops::{Bound, RangeBounds},
 };
 
 use bytecount::num_chars;
-use str_indices::chars;
+use str_indices::{chars, lines_lf, utf16};
 
 /// A Gap buffer. This represents the text of a buffer, and allows for
 /// efficient insertion and deletion of text.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Buffer {
     /// The buffer data
     data: Box<[u8]>,
-    /// start of the gap. Both gap_start and gap_end are the same point, but
-    /// gap_start is never a valid byte index, and gap_end is always used
+    /// start of the gap. Both `gap_start` and `gap_end` are the same point, but
+    /// `gap_start` is never a valid byte index, and `gap_end` is always used
     /// instead.
     gap_start: usize,
     /// The end of the gap in bytes
@@ -26,6 +29,10 @@ pub struct Buffer {
     /// The current cursor.
     cursor: Point,
     total_chars: usize,
+    /// The number of newlines in the buffer
+    total_lines: usize,
+    /// Which line endings are left out when measuring a line
+    line_ending: LineEnding,
 }
 
 impl Display for Buffer {
@@ -48,10 +55,119 @@ impl Debug for Buffer {
             .field("gap_chars", &self.gap_chars)
             .field("cursor", &self.cursor)
             .field("total_chars", &self.total_chars)
+            .field("total_lines", &self.total_lines)
+            .field("line_ending", &self.line_ending)
             .finish()
     }
 }
 
+impl PartialEq for Buffer {
+    fn eq(&self, other: &Self) -> bool {
+        // compare the text, not where the gap happens to be
+        self.len() == other.len() && self.bytes().eq(other.bytes())
+    }
+}
+
+impl Eq for Buffer {}
+
+impl PartialEq<str> for Buffer {
+    fn eq(&self, other: &str) -> bool {
+        self.eq_str(other)
+    }
+}
+
+impl PartialEq<&str> for Buffer {
+    fn eq(&self, other: &&str) -> bool {
+        self.eq_str(other)
+    }
+}
+
+impl Hash for Buffer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Hashers are not required to give the same result for the same bytes
+        // split differently, so feed them in fixed size blocks regardless of
+        // where the gap is.
+        const BLOCK_SIZE: usize = 256;
+        let mut block = [0; BLOCK_SIZE];
+        let mut len = 0;
+        for chunk in self.chunks() {
+            let mut chunk = chunk.as_bytes();
+            while !chunk.is_empty() {
+                let size = chunk.len().min(BLOCK_SIZE - len);
+                block[len..len + size].copy_from_slice(&chunk[..size]);
+                len += size;
+                chunk = &chunk[size..];
+                if len == BLOCK_SIZE {
+                    state.write(&block);
+                    len = 0;
+                }
+            }
+        }
+        state.write(&block[..len]);
+        // same terminator as str
+        state.write_u8(0xff);
+    }
+}
+
+/// Serialized as the text of the buffer along with the char position of the
+/// gap, so the layout does not depend on the internal representation. On
+/// deserialize the cursor is placed at the gap, so the next insert happens
+/// there without moving any text.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Buffer {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Buffer", 2)?;
+        state.serialize_field("text", &self.to_string())?;
+        state.serialize_field("gap", &self.gap_chars)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Buffer {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Repr {
+            text: String,
+            gap: usize,
+        }
+        let Repr { text, gap } = Repr::deserialize(deserializer)?;
+        let mut buffer = Self::from(text);
+        if gap > buffer.total_chars {
+            return Err(serde::de::Error::custom(format!(
+                "gap ({gap}) is out of bounds (len {})",
+                buffer.total_chars
+            )));
+        }
+        buffer.move_gap(gap);
+        buffer.set_cursor(gap);
+        Ok(buffer)
+    }
+}
+
+/// A change to the text of a buffer, in byte offsets. The text from `start`
+/// to `old_end` was replaced by the text from `start` to `new_end`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub start: usize,
+    pub old_end: usize,
+    pub new_end: usize,
+}
+
+/// The line endings that are left out of the length of a line by
+/// [`Buffer::bytes_in_line`], [`Buffer::chars_in_line`] and
+/// [`Buffer::line_end_byte`]. Lines are always split on `\n`, so this does not
+/// change line numbers or where lines start.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Only `\n` is a line ending. A `\r` before it is part of the line.
+    Lf,
+    /// Both `\n` and `\r\n` are line endings.
+    #[default]
+    CrLf,
+}
+
 #[derive(Debug, Default, Copy, Clone)]
 struct Point {
     byte: usize,
@@ -78,31 +194,73 @@ impl From<&str> for Buffer {
                 char: 0,
             },
             total_chars: chars::count(data),
+            total_lines: count_lines(data.as_bytes()),
+            line_ending: LineEnding::default(),
         }
     }
 }
 
 impl From<String> for Buffer {
     fn from(data: String) -> Self {
-        let storage = {
-            let capacity = data.len() + Self::GAP_SIZE;
-            let mut storage = Vec::with_capacity(capacity);
-            storage.resize(Self::GAP_SIZE, 0);
-            storage.extend_from_slice(data.as_bytes());
-            debug_assert_eq!(storage.len(), capacity);
-            storage.into_boxed_slice()
-        };
-        Self {
-            data: storage,
-            gap_start: 0,
-            gap_end: Self::GAP_SIZE,
-            gap_chars: 0,
-            cursor: Point {
-                byte: Self::GAP_SIZE,
-                char: 0,
-            },
-            total_chars: chars::count(&data),
+        Self::from(data.as_str())
+    }
+}
+
+impl FromIterator<char> for Buffer {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        Self::from(iter.into_iter().collect::<String>())
+    }
+}
+
+impl<'a> FromIterator<&'a str> for Buffer {
+    fn from_iter<T: IntoIterator<Item = &'a str>>(iter: T) -> Self {
+        Self::from(iter.into_iter().collect::<String>())
+    }
+}
+
+impl FromIterator<String> for Buffer {
+    fn from_iter<T: IntoIterator<Item = String>>(iter: T) -> Self {
+        Self::from(iter.into_iter().collect::<String>())
+    }
+}
+
+impl IntoIterator for Buffer {
+    type Item = String;
+    type IntoIter = std::option::IntoIter<String>;
+
+    /// Consume the buffer, yielding its text as owned chunks. The gap is moved
+    /// to the end so the storage can be reused for a single chunk without
+    /// copying it into a new allocation.
+    fn into_iter(mut self) -> Self::IntoIter {
+        if self.is_empty() {
+            return None.into_iter();
         }
+        self.move_gap(self.total_chars);
+        let mut storage = self.data.into_vec();
+        storage.truncate(self.gap_start);
+        Some(String::from_utf8(storage).unwrap()).into_iter()
+    }
+}
+
+// The items are collected first so the gap can be grown once for all of
+// them and the cursor is only fixed up once.
+impl Extend<char> for Buffer {
+    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
+        self.push_str(&iter.into_iter().collect::<String>());
+    }
+}
+
+impl<'a> Extend<&'a str> for Buffer {
+    fn extend<T: IntoIterator<Item = &'a str>>(&mut self, iter: T) {
+        self.push_many(&iter.into_iter().collect::<Vec<_>>());
+    }
+}
+
+impl Extend<String> for Buffer {
+    fn extend<T: IntoIterator<Item = String>>(&mut self, iter: T) {
+        let strings: Vec<String> = iter.into_iter().collect();
+        let slices: Vec<&str> = strings.iter().map(String::as_str).collect();
+        self.push_many(&slices);
     }
 }
 
@@ -117,6 +275,89 @@ impl Buffer {
         Self::default()
     }
 
+    /// Create a buffer from `bytes`, checking that they are valid utf8.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` is not valid utf8.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, std::str::Utf8Error> {
+        std::str::from_utf8(bytes).map(Self::from)
+    }
+
+    /// Create a buffer that reuses the allocation of `data` for its storage,
+    /// with the gap and the cursor placed at the end of the text. Unlike
+    /// `From<String>`, which copies the text behind a gap at the front, this
+    /// means the first insert at the cursor appends without moving any text.
+    ///
+    /// The storage has to be exactly the length of the text plus the gap, so
+    /// the allocation is only kept as is when the string's capacity is
+    /// already that size. Otherwise it is grown or shrunk once, which may copy
+    /// the text.
+    #[must_use]
+    pub fn from_string(data: String) -> Self {
+        let len = data.len();
+        let total_chars = chars::count(&data);
+        let total_lines = count_lines(data.as_bytes());
+        let mut storage = data.into_bytes();
+        storage.reserve_exact(Self::GAP_SIZE);
+        storage.resize(len + Self::GAP_SIZE, 0);
+        let data = storage.into_boxed_slice();
+        Self {
+            gap_start: len,
+            gap_end: data.len(),
+            gap_chars: total_chars,
+            cursor: Point {
+                byte: data.len(),
+                char: total_chars,
+            },
+            total_chars,
+            total_lines,
+            line_ending: LineEnding::default(),
+            data,
+        }
+    }
+
+    /// Create a buffer from the contents of `reader`. The text is read
+    /// directly into the buffer storage behind the gap instead of through an
+    /// intermediate `String`. The storage still grows while reading and is
+    /// shrunk to fit afterwards, so both can reallocate and peak memory can be
+    /// about twice the size of the text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails or the text is not valid utf8.
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut storage = vec![0; Self::GAP_SIZE];
+        reader.read_to_end(&mut storage)?;
+        let text = std::str::from_utf8(&storage[Self::GAP_SIZE..])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let total_chars = chars::count(text);
+        let total_lines = count_lines(text.as_bytes());
+        Ok(Self {
+            data: storage.into_boxed_slice(),
+            gap_start: 0,
+            gap_end: Self::GAP_SIZE,
+            gap_chars: 0,
+            cursor: Point {
+                byte: Self::GAP_SIZE,
+                char: 0,
+            },
+            total_chars,
+            total_lines,
+            line_ending: LineEnding::default(),
+        })
+    }
+
+    /// Write the text of the buffer to `writer`, skipping the gap.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.data[..self.gap_start])?;
+        writer.write_all(&self.data[self.gap_end..])
+    }
+
     fn grow(&mut self, slice: &str) {
         let new_capacity = {
             let pre_gap = self.gap_start;
@@ -144,13 +385,62 @@ impl Buffer {
         self.gap_chars += num_chars;
         self.cursor.char = self.gap_chars;
         self.total_chars += num_chars;
+        self.total_lines += count_lines(slice.as_bytes());
+    }
+
+    /// Make sure the gap has room for at least `additional` bytes, so that
+    /// inserting that much text at the gap will not reallocate.
+    pub fn reserve_gap(&mut self, additional: usize) {
+        if self.gap_len() < additional {
+            self.resize_gap(additional);
+        }
+    }
+
+    /// Shrink the gap back down to the default size if it has grown larger,
+    /// freeing the extra memory. The text is unchanged.
+    pub fn shrink_gap(&mut self) {
+        if self.gap_len() > Self::GAP_SIZE {
+            self.resize_gap(Self::GAP_SIZE);
+        }
+    }
+
+    fn resize_gap(&mut self, gap_len: usize) {
+        let post_gap = self.data.len() - self.gap_end;
+        let new_end = self.gap_start + gap_len;
+        let new_storage = {
+            let mut buffer = Vec::with_capacity(new_end + post_gap);
+            buffer.extend_from_slice(&self.data[..self.gap_start]);
+            buffer.resize(new_end, 0);
+            buffer.extend_from_slice(&self.data[self.gap_end..]);
+            buffer.into_boxed_slice()
+        };
+        if self.cursor.byte >= self.gap_end {
+            self.cursor.byte = self.cursor.byte - self.gap_end + new_end;
+        }
+        self.data = new_storage;
+        self.gap_end = new_end;
     }
 
+    /// Insert `chr` at the cursor. The char is encoded on the stack, so this
+    /// does not allocate unless the gap needs to grow.
     pub fn insert_char(&mut self, chr: char) {
         let buf = &mut [0; 4];
         self.insert(chr.encode_utf8(buf));
     }
 
+    /// Insert `chr` at the char position `pos`. Like
+    /// [`insert_char`](Self::insert_char) the char is encoded on the stack.
+    /// The cursor is moved to after the char. Returns the [`Edit`] that was
+    /// made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn insert_char_at(&mut self, pos: usize, chr: char) -> Edit {
+        let buf = &mut [0; 4];
+        self.insert_str(pos, chr.encode_utf8(buf))
+    }
+
     pub fn insert(&mut self, slice: &str) {
         // if gap is not at cursor, move it there
         if self.gap_chars != self.cursor.char {
@@ -161,14 +451,239 @@ impl Buffer {
             // TODO: grow the gap and move the cursor in one go
             self.grow(slice);
         } else {
-            let new_slice = &mut self.data[self.gap_start..(self.gap_start + slice.len())];
-            new_slice.copy_from_slice(slice.as_bytes());
-            self.gap_start += slice.len();
-            let num_chars = chars::count(slice);
-            self.gap_chars += num_chars;
-            self.cursor.char += num_chars;
-            self.total_chars += num_chars;
+            self.cursor.char += self.fill_gap(slice);
+        }
+    }
+
+    /// Copy `slice` into the start of the gap, returning the number of chars
+    /// written. The gap must already be large enough and the cursor is not
+    /// updated.
+    fn fill_gap(&mut self, slice: &str) -> usize {
+        let new_slice = &mut self.data[self.gap_start..(self.gap_start + slice.len())];
+        new_slice.copy_from_slice(slice.as_bytes());
+        self.gap_start += slice.len();
+        let num_chars = chars::count(slice);
+        self.gap_chars += num_chars;
+        self.total_chars += num_chars;
+        self.total_lines += count_lines(slice.as_bytes());
+        num_chars
+    }
+
+    /// Insert `slice` at the character position `pos`. The cursor is moved to
+    /// the end of the inserted text. Returns the [`Edit`] that was made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn insert_str(&mut self, pos: usize, slice: &str) -> Edit {
+        assert!(
+            pos <= self.total_chars,
+            "insert position ({pos}) is out of bounds (len {})",
+            self.total_chars
+        );
+        self.insert_at_byte(self.char_to_byte(pos), slice)
+    }
+
+    /// Insert `slice` at the byte offset `byte`. The cursor is moved to the end
+    /// of the inserted text. Returns the [`Edit`] that was made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is past the end of the buffer or not on a char
+    /// boundary.
+    pub fn insert_at_byte(&mut self, byte: usize, slice: &str) -> Edit {
+        assert!(
+            self.is_char_boundary(byte),
+            "insert byte ({byte}) is out of bounds or not on a char boundary (len {})",
+            self.len()
+        );
+        // Moving the gap only counts the chars it moves across, so afterwards
+        // the char position of the insert is known without scanning from the
+        // start of the buffer.
+        self.move_gap_to_raw_byte(self.to_raw_byte(byte));
+        self.cursor = Point {
+            byte: self.gap_end,
+            char: self.gap_chars,
+        };
+        self.insert(slice);
+        // inserting always leaves the gap at the end of the new text
+        let start = self.gap_start - slice.len();
+        Edit {
+            start,
+            old_end: start,
+            new_end: self.gap_start,
+        }
+    }
+
+    /// Like [`insert_str`](Self::insert_str), but returns `None` instead of
+    /// panicking if `pos` is past the end of the buffer.
+    pub fn try_insert_str(&mut self, pos: usize, slice: &str) -> Option<Edit> {
+        (pos <= self.total_chars).then(|| self.insert_str(pos, slice))
+    }
+
+    /// Insert each of `slices` in order at the char position `pos`. The gap is
+    /// moved and grown once for all of them. The cursor is moved to the end
+    /// of the inserted text. Returns the [`Edit`] that was made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn insert_many(&mut self, pos: usize, slices: &[&str]) -> Edit {
+        self.move_gap(pos);
+        self.set_cursor(pos);
+        let start = self.gap_start;
+        let len = slices.iter().map(|slice| slice.len()).sum();
+        if self.gap_len() < len {
+            // leave room after the text like push_many, so that the next
+            // insert does not reallocate
+            self.resize_gap(len + Self::GAP_SIZE);
+        }
+        for slice in slices {
+            self.insert(slice);
+        }
+        Edit {
+            start,
+            old_end: start,
+            new_end: self.gap_start,
+        }
+    }
+
+    /// Insert a copy of the text of `other` at the char position `pos`. The
+    /// cursor is moved to the end of the inserted text. Returns the [`Edit`]
+    /// that was made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn insert_buffer(&mut self, pos: usize, other: &Self) -> Edit {
+        let (before, after) = other.as_slices();
+        self.insert_many(pos, &[before, after])
+    }
+
+    /// Move the text of `other` to the end of the buffer. If the buffer is
+    /// empty, the storage of `other` is taken over instead of copied. The
+    /// cursor is not moved. Use [`insert_buffer`](Self::insert_buffer) to
+    /// copy from a buffer that is still needed. Returns the [`Edit`] that was
+    /// made.
+    pub fn append(&mut self, other: Self) -> Edit {
+        if self.is_empty() {
+            let line_ending = self.line_ending;
+            *self = other;
+            self.line_ending = line_ending;
+            self.set_cursor(0);
+            Edit {
+                start: 0,
+                old_end: 0,
+                new_end: self.len(),
+            }
+        } else {
+            let (before, after) = other.as_slices();
+            self.push_many(&[before, after])
+        }
+    }
+
+    /// Append `slice` to the end of the buffer. The cursor is not moved.
+    /// Returns the [`Edit`] that was made.
+    pub fn push_str(&mut self, slice: &str) -> Edit {
+        self.push_many(&[slice])
+    }
+
+    /// Append each of `slices` in order to the end of the buffer. The gap is
+    /// grown once for all of them, and the cursor is not moved.
+    fn push_many(&mut self, slices: &[&str]) -> Edit {
+        let start = self.len();
+        let len = slices.iter().map(|slice| slice.len()).sum();
+        let edit = Edit {
+            start,
+            old_end: start,
+            new_end: start + len,
+        };
+        if len == 0 {
+            return edit;
         }
+        self.move_gap(self.total_chars);
+        if self.gap_len() < len {
+            // leave room after the text like grow does, so that repeated
+            // appends do not reallocate every time
+            self.resize_gap(len + Self::GAP_SIZE);
+        }
+        // A cursor before the end keeps its position. A cursor at the end is
+        // stored after the gap, but will now be before the new text, so it
+        // moves to the start of the gap. Its char position does not change
+        // either way, so it never needs to be recalculated.
+        if self.cursor.byte >= self.gap_end {
+            self.cursor.byte = self.gap_start;
+        }
+        for slice in slices {
+            self.fill_gap(slice);
+        }
+        edit
+    }
+
+    /// Split the buffer at the char position `pos`, returning everything after
+    /// it as a new buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    #[must_use]
+    pub fn split_off(&mut self, pos: usize) -> Self {
+        let tail = self.slice(pos, self.total_chars);
+        self.delete_region(pos, self.total_chars);
+        tail
+    }
+
+    /// Remove every char at or after the char position `char_len`. Truncating
+    /// to the current length or beyond does nothing. Returns the [`Edit`] that
+    /// was made, which is empty if nothing was removed.
+    pub fn truncate(&mut self, char_len: usize) -> Edit {
+        // positions past the end are clamped, so this is an empty edit at the
+        // end when there is nothing to remove
+        self.delete_region(char_len.min(self.total_chars), self.total_chars)
+    }
+
+    /// Remove the char at position `pos` and return it, or `None` if `pos` is
+    /// out of bounds.
+    pub fn remove_char(&mut self, pos: usize) -> Option<char> {
+        let chr = self.char_at(pos)?;
+        self.delete_region(pos, pos + 1);
+        Some(chr)
+    }
+
+    /// Keep only the chars for which `f` returns true. The buffer storage is
+    /// reused if it is large enough, and the cursor is moved to the start.
+    pub fn retain<F: FnMut(char) -> bool>(&mut self, mut f: F) {
+        let text: String = self.chars().filter(|chr| f(*chr)).collect();
+        self.clear();
+        self.push_str(&text);
+    }
+
+    /// Remove every trailing `ch` from the end of the buffer. Returns the
+    /// [`Edit`] that was made.
+    pub fn trim_end_matches(&mut self, ch: char) -> Edit {
+        let count = self.chars().rev().take_while(|c| *c == ch).count();
+        self.truncate(self.total_chars - count)
+    }
+
+    /// Remove every leading `ch` from the start of the buffer. Returns the
+    /// [`Edit`] that was made.
+    pub fn trim_start_matches(&mut self, ch: char) -> Edit {
+        let count = self.chars().take_while(|c| *c == ch).count();
+        self.delete_region(0, count)
+    }
+
+    /// Remove all text from the buffer. The storage is kept and becomes gap,
+    /// so refilling the buffer does not need to reallocate.
+    pub fn clear(&mut self) {
+        self.gap_start = 0;
+        self.gap_end = self.data.len();
+        self.gap_chars = 0;
+        self.cursor = Point {
+            byte: self.gap_end,
+            char: 0,
+        };
+        self.total_chars = 0;
+        self.total_lines = 0;
     }
 
     pub fn delete_backwards(&mut self, size: usize) {
@@ -180,16 +695,49 @@ impl Buffer {
         self.delete_region(self.cursor.char, self.cursor.char + size);
     }
 
-    pub fn delete_region(&mut self, beg: usize, end: usize) {
+    /// Delete the chars between `beg` and `end`, in either order. Positions
+    /// past the end of the buffer are clamped. Returns the [`Edit`] that was
+    /// made.
+    pub fn delete_region(&mut self, beg: usize, end: usize) -> Edit {
         let (mut beg, mut end) = (beg, end);
         if beg > end {
             (beg, end) = (end, beg);
         }
-        let end = self.char_to_byte(end.min(self.total_chars));
-        let beg = self.char_to_byte(beg.min(self.total_chars));
+        let end = self.char_to_raw_byte(end.min(self.total_chars));
+        let beg = self.char_to_raw_byte(beg.min(self.total_chars));
+        let edit = Edit {
+            start: self.to_logical_byte(beg),
+            old_end: self.to_logical_byte(end),
+            new_end: self.to_logical_byte(beg),
+        };
         if end != beg {
             self.delete_byte_region(beg, end);
         }
+        edit
+    }
+
+    /// Replace the chars from `beg` to `end` with `slice`. The cursor is moved
+    /// to the end of the inserted text. Returns the [`Edit`] that was made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `beg` is greater than `end` or `end` is past the end of the
+    /// buffer.
+    pub fn replace_range(&mut self, beg: usize, end: usize, slice: &str) -> Edit {
+        assert!(
+            beg <= end && end <= self.total_chars,
+            "replace range ({beg}-{end}) is out of bounds (len {})",
+            self.total_chars
+        );
+        // deleting leaves the gap at beg, so the insert does not need to move
+        // it again
+        let deleted = self.delete_region(beg, end);
+        let inserted = self.insert_str(beg, slice);
+        Edit {
+            start: deleted.start,
+            old_end: deleted.old_end,
+            new_end: inserted.new_end,
+        }
     }
 
     fn delete_byte_region(&mut self, beg: usize, end: usize) {
@@ -215,6 +763,7 @@ impl Buffer {
             self.gap_chars -= num_chars(&self.data[beg..self.gap_start]);
             let deleted_chars = num_chars(&self.data[beg..end]);
             self.total_chars -= deleted_chars;
+            self.total_lines -= count_lines(&self.data[beg..end]);
             let new_end = self.gap_end - (self.gap_start - end);
             // shift data
             self.data.copy_within(end..self.gap_start, new_end);
@@ -246,6 +795,7 @@ impl Buffer {
             // update character count
             let deleted_chars = num_chars(&self.data[beg..end]);
             self.total_chars -= deleted_chars;
+            self.total_lines -= count_lines(&self.data[beg..end]);
             self.gap_chars += num_chars(&self.data[self.gap_end..beg]);
             // shift data
             self.data.copy_within(self.gap_end..beg, self.gap_start);
@@ -280,6 +830,8 @@ impl Buffer {
             let total_chars = chars_before + chars_after;
             self.gap_chars -= chars_before;
             self.total_chars -= total_chars;
+            self.total_lines -= count_lines(&self.data[beg..self.gap_start]);
+            self.total_lines -= count_lines(&self.data[self.gap_end..end]);
             // update gap position
             self.gap_start = beg;
             self.gap_end = end;
@@ -333,8 +885,24 @@ impl Buffer {
         }
     }
 
-    fn move_gap(&mut self, pos: usize) {
-        let pos = self.char_to_byte(pos);
+    /// Move the gap to the char position `pos`. This does not change the text
+    /// of the buffer, but makes edits near `pos` cheaper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn move_gap(&mut self, pos: usize) {
+        assert!(
+            pos <= self.total_chars,
+            "gap position ({pos}) is out of bounds (len {})",
+            self.total_chars
+        );
+        self.move_gap_to_raw_byte(self.char_to_raw_byte(pos));
+    }
+
+    /// Move the gap to the index `pos` into `data`, which must not be inside
+    /// the gap.
+    fn move_gap_to_raw_byte(&mut self, pos: usize) {
         assert!(pos <= self.data.len(), "attempt to move gap out of bounds");
         self.assert_char_boundary(pos);
         if pos < self.gap_start {
@@ -371,117 +939,1419 @@ impl Buffer {
 
     pub fn set_cursor(&mut self, pos: usize) {
         let pos = pos.min(self.total_chars);
-        let byte_pos = self.char_to_byte(pos);
+        let byte_pos = self.char_to_raw_byte(pos);
         self.cursor = Point {
             byte: byte_pos,
             char: pos,
         };
     }
 
+    /// The length of the buffer in bytes. This does not include the gap.
+    #[inline]
     pub const fn len(&self) -> usize {
         self.data.len() - self.gap_len()
     }
 
+    /// The length of the buffer in chars.
+    #[inline]
     pub const fn len_chars(&self) -> usize {
         self.total_chars
     }
 
+    /// The number of lines in the buffer. This is always one more than the
+    /// number of newlines, so an empty buffer has a single line.
+    pub const fn len_lines(&self) -> usize {
+        self.total_lines + 1
+    }
+
     pub const fn is_empty(&self) -> bool {
         self.total_chars == 0
     }
 
-    const fn gap_len(&self) -> usize {
-        self.gap_end - self.gap_start
+    /// The number of bytes the buffer can hold without reallocating. This is
+    /// the text plus the gap, and is all the heap memory the buffer uses.
+    pub const fn capacity(&self) -> usize {
+        self.data.len()
     }
 
-    fn char_to_byte(&self, pos: usize) -> usize {
-        if pos == 0 {
-            return if self.gap_start == 0 { self.gap_end } else { 0 };
-        }
-        if pos == self.total_chars {
-            return self.data.len();
+    /// Returns the byte offset of the gap and its length in bytes.
+    pub const fn gap(&self) -> (usize, usize) {
+        (self.gap_start, self.gap_len())
+    }
+
+    /// The number of extended grapheme clusters in the buffer. Clusters that
+    /// span the gap are only counted once.
+    #[cfg(feature = "graphemes")]
+    pub fn len_graphemes(&self) -> usize {
+        use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete, UnicodeSegmentation};
+        let (before, after) = self.as_slices();
+        // Boundaries before the gap only depend on the text before it, but
+        // boundaries after the gap can depend on the text before it (e.g.
+        // pairs of regional indicators), so that side is given context.
+        let mut count = before.graphemes(true).count();
+        let mut cursor = GraphemeCursor::new(self.gap_start, self.len(), true);
+        loop {
+            match cursor.next_boundary(after, self.gap_start) {
+                Ok(Some(_)) => count += 1,
+                Ok(None) => break,
+                Err(GraphemeIncomplete::PreContext(end)) => {
+                    cursor.provide_context(&before[..end], 0);
+                }
+                Err(err) => unreachable!("unexpected grapheme error: {err:?}"),
+            }
         }
-        // (byte position, char positions) pairs sorted in ascending order
-        let positions = {
-            let start = (self.gap_start, self.gap_chars);
-            let end = (self.gap_end, self.gap_chars);
-            let total = (self.data.len(), self.total_chars);
-            let cursor = (self.cursor.byte, self.cursor.char);
-            if self.cursor.char < self.gap_chars {
-                [(0, 0), cursor, start, end, total]
-            } else {
-                [(0, 0), start, end, cursor, total]
+        if !before.is_empty() && !after.is_empty() {
+            // a cluster that spans the gap was counted on both sides
+            let mut cursor = GraphemeCursor::new(self.gap_start, self.len(), true);
+            let mut is_boundary = cursor.is_boundary(after, self.gap_start);
+            if let Err(GraphemeIncomplete::PreContext(end)) = is_boundary {
+                cursor.provide_context(&before[..end], 0);
+                is_boundary = cursor.is_boundary(after, self.gap_start);
             }
-        };
+            if is_boundary == Ok(false) {
+                count -= 1;
+            }
+        }
+        count
+    }
 
-        // find which positions window the char position falls into
-        let window = positions
-            .windows(2)
-            .find(|slice| slice[0].1 <= pos && pos < slice[1].1);
+    /// Returns the text before and after the gap.
+    pub fn as_slices(&self) -> (&str, &str) {
+        (self.to_str(..self.gap_start), self.to_str(self.gap_end..))
+    }
 
-        let Some([(beg_byte, beg_char), (end_byte, end_char)]) = window else {
-            unreachable!("char position ({pos}) did not fall into any window");
-        };
+    /// Returns the text of the buffer as a single `&str` if the gap is at the
+    /// start or end, and `None` if the gap splits the text.
+    pub fn as_contiguous(&self) -> Option<&str> {
+        match self.as_slices() {
+            (text, "") | ("", text) => Some(text),
+            _ => None,
+        }
+    }
 
-        self.assert_char_boundary(*beg_byte);
-        self.assert_char_boundary(*end_byte);
+    /// Returns an iterator over the text on either side of the gap. Empty
+    /// chunks are skipped.
+    pub fn chunks(&self) -> impl DoubleEndedIterator<Item = &str> {
+        let (before, after) = self.as_slices();
+        [before, after]
+            .into_iter()
+            .filter(|chunk| !chunk.is_empty())
+    }
 
-        let num_chars = end_char - beg_char;
-        if end_byte - beg_byte == num_chars {
-            // the slice is ascii text, so we can just index into it
-            beg_byte + (pos - beg_char)
+    /// Like [`chunks`](Self::chunks), but each chunk is paired with the byte
+    /// offset where it starts. Offsets do not include the gap.
+    pub fn chunk_indices(&self) -> impl DoubleEndedIterator<Item = (usize, &str)> {
+        let (before, after) = self.as_slices();
+        [(0, before), (self.gap_start, after)]
+            .into_iter()
+            .filter(|(_, chunk)| !chunk.is_empty())
+    }
+
+    /// Returns true if the text of the buffer is `other`. This compares each
+    /// side of the gap in place instead of building a `String`.
+    pub fn eq_str(&self, other: &str) -> bool {
+        let other = other.as_bytes();
+        if self.len() != other.len() {
+            return false;
+        }
+        let (before, after) = other.split_at(self.gap_start);
+        self.data[..self.gap_start] == *before && self.data[self.gap_end..] == *after
+    }
+
+    /// Returns the chunk containing the byte offset `byte` and the byte offset
+    /// of the start of that chunk. Passing the length of the buffer returns
+    /// the last chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is past the end of the buffer.
+    pub fn chunk_at_byte(&self, byte: usize) -> (&str, usize) {
+        assert!(
+            byte <= self.len(),
+            "byte ({byte}) is out of bounds (len {})",
+            self.len()
+        );
+        let (before, after) = self.as_slices();
+        if byte < self.gap_start || after.is_empty() {
+            (before, 0)
         } else {
-            let string = self.to_str(*beg_byte..*end_byte);
-            let byte_idx = chars::to_byte_idx(string, pos - beg_char);
-            beg_byte + byte_idx
+            (after, self.gap_start)
         }
     }
 
-    fn to_str(&self, range: impl std::slice::SliceIndex<[u8], Output = [u8]>) -> &str {
-        // TODO: remove this check once we are confident the code is correct
-        std::str::from_utf8(&self.data[range]).unwrap()
+    /// Returns an iterator over the chars of the buffer. Use `rev` to iterate
+    /// backwards.
+    pub fn chars(&self) -> impl DoubleEndedIterator<Item = char> + '_ {
+        self.chunks().flat_map(str::chars)
     }
 
-    fn assert_char_boundary(&self, pos: usize) {
-        if pos == self.gap_start {
-            return;
-        }
-        let is_boundary = match self.data.get(pos) {
-            Some(byte) => Self::is_char_boundary(*byte),
-            None => pos == self.data.len(),
-        };
-        assert!(is_boundary, "position ({pos}) not on utf8 boundary");
+    /// Returns an iterator over the chars of the buffer and their char
+    /// positions. Unlike [`str::char_indices`], the positions are counted in
+    /// chars, not bytes.
+    pub fn char_indices(&self) -> impl Iterator<Item = (usize, char)> + '_ {
+        self.chars().enumerate()
     }
 
-    #[allow(clippy::cast_possible_wrap)]
-    const fn is_char_boundary(byte: u8) -> bool {
-        // This is bit magic equivalent to: b < 128 || b >= 192
-        (byte as i8) >= -0x40
+    /// Returns an iterator over the bytes of the buffer, skipping the gap.
+    pub fn bytes(&self) -> impl DoubleEndedIterator<Item = u8> + '_ {
+        self.byte_slice(0, self.len())
     }
-}
 
-#[allow(dead_code)]
-#[cfg(test)]
-impl Buffer {
-    // used to convert indexes from the fuzzer
-    fn wrap(&self, pos: usize) -> usize {
-        let pos = pos % (self.len() + 2);
-        println!("pos: {:?}", pos);
-        pos
+    /// Returns an iterator over the bytes from `beg` to `end`, skipping the
+    /// gap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `beg` is greater than `end` or `end` is past the end of the
+    /// buffer.
+    pub fn byte_slice(&self, beg: usize, end: usize) -> impl DoubleEndedIterator<Item = u8> + '_ {
+        assert!(
+            beg <= end && end <= self.len(),
+            "byte slice ({beg}-{end}) is out of bounds (len {})",
+            self.len()
+        );
+        let split = self.gap_start.clamp(beg, end);
+        let before = &self.data[beg..split];
+        let after = &self.data[self.to_raw_byte(split)..self.to_raw_byte(end)];
+        before.iter().chain(after).copied()
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    /// Returns an iterator over the lines of the buffer, without their line
+    /// endings. This follows the same rules as [`str::lines`].
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        let mut chars = self.chars().peekable();
+        std::iter::from_fn(move || {
+            chars.peek()?;
+            let mut line = String::new();
+            for chr in chars.by_ref() {
+                if chr == '\n' {
+                    // only a \r before a \n is part of the line ending
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                    break;
+                }
+                line.push(chr);
+            }
+            Some(line)
+        })
+    }
 
-    #[test]
-    fn create() {
-        let string = "hello buffer";
-        let buffer = Buffer::from(string);
-        assert_eq!(buffer.data.len(), string.len() + Buffer::GAP_SIZE);
-        assert_eq!(buffer.gap_end, Buffer::GAP_SIZE);
-        assert_eq!(buffer.gap_start, 0);
+    /// Split the buffer into a buffer for each line, without their line
+    /// endings. Lines follow the same rules as [`lines`](Self::lines), so a
+    /// trailing newline does not produce an empty last buffer.
+    pub fn into_line_buffers(self) -> Vec<Self> {
+        self.lines().map(Self::from).collect()
+    }
+
+    /// Returns the char at position `pos`, or `None` if it is out of bounds.
+    pub fn char_at(&self, pos: usize) -> Option<char> {
+        if pos >= self.total_chars {
+            return None;
+        }
+        let (beg, end) = (self.char_to_byte(pos), self.char_to_byte(pos + 1));
+        let (before, after) = self.byte_range(beg, end);
+        before.chars().chain(after.chars()).next()
+    }
+
+    /// Returns a new buffer containing the chars from `beg` to `end`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `beg` is greater than `end` or `end` is past the end of the
+    /// buffer.
+    #[must_use]
+    pub fn slice(&self, beg: usize, end: usize) -> Self {
+        let mut slice = Self::from(&*self.slice_str(beg, end));
+        slice.line_ending = self.line_ending;
+        slice
+    }
+
+    /// Returns the text of the chars from `beg` to `end`. This is borrowed
+    /// from the buffer unless the gap splits the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `beg` is greater than `end` or `end` is past the end of the
+    /// buffer.
+    pub fn slice_str(&self, beg: usize, end: usize) -> Cow<'_, str> {
+        assert!(
+            beg <= end && end <= self.total_chars,
+            "slice ({beg}-{end}) is out of bounds (len {})",
+            self.total_chars
+        );
+        match self.byte_range(self.char_to_byte(beg), self.char_to_byte(end)) {
+            (text, "") | ("", text) => Cow::Borrowed(text),
+            (before, after) => Cow::Owned([before, after].concat()),
+        }
+    }
+
+    /// Returns the char position of the first occurrence of `pattern`.
+    pub fn find(&self, pattern: &str) -> Option<usize> {
+        self.find_byte(pattern, 0)
+            .map(|byte| self.byte_to_char(byte))
+    }
+
+    /// Returns an iterator over the char positions of every non-overlapping
+    /// occurrence of `pattern`. An empty pattern has no matches.
+    pub fn find_all<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = usize> + 'a {
+        // track the last match so we only count the chars between matches
+        let mut last = (0, 0);
+        std::iter::from_fn(move || {
+            if pattern.is_empty() {
+                return None;
+            }
+            let (last_byte, last_char) = last;
+            let byte = self.find_byte(pattern, last_byte)?;
+            let (before, after) = self.byte_range(last_byte, byte);
+            let char = last_char + chars::count(before) + chars::count(after);
+            last = (byte + pattern.len(), char + chars::count(pattern));
+            Some(char)
+        })
+    }
+
+    /// Returns the char position of the last occurrence of `pattern`.
+    pub fn rfind(&self, pattern: &str) -> Option<usize> {
+        self.rfind_byte(pattern).map(|byte| self.byte_to_char(byte))
+    }
+
+    /// Returns the byte offset of the first occurrence of `pattern` at or
+    /// after the byte offset `from`.
+    fn find_byte(&self, pattern: &str, from: usize) -> Option<usize> {
+        if from < self.gap_start {
+            if let Some(idx) = self.to_str(from..self.gap_start).find(pattern) {
+                return Some(from + idx);
+            }
+        }
+        let (seam_start, seam) = self.gap_seam(pattern.len());
+        let skip = from.saturating_sub(seam_start);
+        if let Some(idx) = seam.get(skip..).and_then(|seam| seam.find(pattern)) {
+            return Some(seam_start + skip + idx);
+        }
+        let after_start = from.max(self.gap_start);
+        let after = self.to_str(self.to_raw_byte(after_start)..);
+        after.find(pattern).map(|idx| after_start + idx)
+    }
+
+    fn rfind_byte(&self, pattern: &str) -> Option<usize> {
+        if let Some(idx) = self.to_str(self.gap_end..).rfind(pattern) {
+            return Some(self.gap_start + idx);
+        }
+        let (seam_start, seam) = self.gap_seam(pattern.len());
+        if let Some(idx) = seam.rfind(pattern) {
+            return Some(seam_start + idx);
+        }
+        self.to_str(..self.gap_start).rfind(pattern)
+    }
+
+    /// Returns the text surrounding the gap that a match of `len` bytes could
+    /// span, along with the byte offset where it starts.
+    fn gap_seam(&self, len: usize) -> (usize, String) {
+        let reach = len.saturating_sub(1);
+        let mut beg = self.gap_start.saturating_sub(reach);
+        while beg < self.gap_start && !is_char_boundary(self.data[beg]) {
+            beg -= 1;
+        }
+        let mut end = self.gap_end + reach.min(self.data.len() - self.gap_end);
+        while end < self.data.len() && !is_char_boundary(self.data[end]) {
+            end += 1;
+        }
+        let mut seam = String::from(self.to_str(beg..self.gap_start));
+        seam.push_str(self.to_str(self.gap_end..end));
+        (beg, seam)
+    }
+
+    /// Returns the byte offset of the start of `line`. Passing one past the
+    /// last line returns the length of the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is more than one past the last line.
+    pub fn line_to_byte(&self, line: usize) -> usize {
+        assert!(
+            line <= self.len_lines(),
+            "line ({line}) is out of bounds (lines {})",
+            self.len_lines()
+        );
+        if line > self.total_lines {
+            return self.len();
+        }
+        let before_lines = count_lines(&self.data[..self.gap_start]);
+        if line <= before_lines {
+            lines_lf::to_byte_idx(self.to_str(..self.gap_start), line)
+        } else {
+            let after = self.to_str(self.gap_end..);
+            self.gap_start + lines_lf::to_byte_idx(after, line - before_lines)
+        }
+    }
+
+    /// Like [`line_to_byte`](Self::line_to_byte), but returns `None` instead
+    /// of panicking if `line` is more than one past the last line.
+    pub fn try_line_to_byte(&self, line: usize) -> Option<usize> {
+        (line <= self.len_lines()).then(|| self.line_to_byte(line))
+    }
+
+    /// Like [`byte_to_line`](Self::byte_to_line), but returns `None` instead
+    /// of panicking if `byte` is past the end of the buffer.
+    pub fn try_byte_to_line(&self, byte: usize) -> Option<usize> {
+        (byte <= self.len()).then(|| self.byte_to_line(byte))
+    }
+
+    /// Returns which line endings are left out when measuring a line. The
+    /// default is [`LineEnding::CrLf`].
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Set which line endings are left out when measuring a line. The text is
+    /// unchanged.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// Returns the length of `line` in bytes, not counting its line ending.
+    /// See [`LineEnding`] for what counts as a line ending.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is past the last line.
+    pub fn bytes_in_line(&self, line: usize) -> usize {
+        let (beg, end) = self.line_byte_range(line);
+        end - beg
+    }
+
+    /// Returns the length of `line` in chars, not counting its line ending.
+    /// See [`LineEnding`] for what counts as a line ending.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is past the last line.
+    pub fn chars_in_line(&self, line: usize) -> usize {
+        let (beg, end) = self.line_byte_range(line);
+        self.byte_to_char(end) - self.byte_to_char(beg)
+    }
+
+    /// Returns the byte offset of the start of the line containing `byte`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is past the end of the buffer.
+    pub fn line_start_byte(&self, byte: usize) -> usize {
+        self.line_to_byte(self.byte_to_line(byte))
+    }
+
+    /// Returns the byte offset of the end of the line containing `byte`,
+    /// before its line ending.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is past the end of the buffer.
+    pub fn line_end_byte(&self, byte: usize) -> usize {
+        self.line_byte_range(self.byte_to_line(byte)).1
+    }
+
+    fn line_byte_range(&self, line: usize) -> (usize, usize) {
+        assert!(
+            line < self.len_lines(),
+            "line ({line}) is out of bounds (lines {})",
+            self.len_lines()
+        );
+        let beg = self.line_to_byte(line);
+        let end = if line < self.total_lines {
+            // exclude the newline
+            self.line_to_byte(line + 1) - 1
+        } else {
+            self.len()
+        };
+        // exclude the carriage return of a crlf
+        let is_crlf = self.line_ending == LineEnding::CrLf
+            && line < self.total_lines
+            && end > beg
+            && self.byte_slice(end - 1, end).next() == Some(b'\r');
+        (beg, if is_crlf { end - 1 } else { end })
+    }
+
+    /// Returns the zero-based line containing the byte offset `byte`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is past the end of the buffer.
+    pub fn byte_to_line(&self, byte: usize) -> usize {
+        assert!(
+            byte <= self.len(),
+            "byte ({byte}) is out of bounds (len {})",
+            self.len()
+        );
+        self.raw_byte_to_line(self.to_raw_byte(byte))
+    }
+
+    /// Returns the zero-based line containing the char position `pos`. This
+    /// is the same as `byte_to_line(char_to_byte(pos))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn char_to_line(&self, pos: usize) -> usize {
+        assert!(
+            pos <= self.total_chars,
+            "char ({pos}) is out of bounds (len {})",
+            self.total_chars
+        );
+        self.raw_byte_to_line(self.char_to_raw_byte(pos))
+    }
+
+    /// Returns the char position of the start of `line`. Passing one past the
+    /// last line returns the length of the buffer in chars.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is more than one past the last line.
+    pub fn line_to_char(&self, line: usize) -> usize {
+        self.byte_to_char(self.line_to_byte(line))
+    }
+
+    fn raw_byte_to_line(&self, raw: usize) -> usize {
+        if raw <= self.gap_start {
+            count_lines(&self.data[..raw])
+        } else {
+            count_lines(&self.data[..self.gap_start]) + count_lines(&self.data[self.gap_end..raw])
+        }
+    }
+
+    /// Converts a char position to a byte offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn char_to_byte(&self, pos: usize) -> usize {
+        assert!(
+            pos <= self.total_chars,
+            "char ({pos}) is out of bounds (len {})",
+            self.total_chars
+        );
+        self.to_logical_byte(self.char_to_raw_byte(pos))
+    }
+
+    /// Like [`char_to_byte`](Self::char_to_byte), but returns `None` instead
+    /// of panicking if `pos` is past the end of the buffer.
+    pub fn try_char_to_byte(&self, pos: usize) -> Option<usize> {
+        (pos <= self.total_chars).then(|| self.char_to_byte(pos))
+    }
+
+    /// Converts a byte offset to a char position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is past the end of the buffer or not on a char
+    /// boundary.
+    pub fn byte_to_char(&self, byte: usize) -> usize {
+        assert!(
+            byte <= self.len(),
+            "byte ({byte}) is out of bounds (len {})",
+            self.len()
+        );
+        let raw = self.to_raw_byte(byte);
+        self.assert_char_boundary(raw);
+        if byte <= self.gap_start {
+            num_chars(&self.data[..byte])
+        } else {
+            self.gap_chars + num_chars(&self.data[self.gap_end..raw])
+        }
+    }
+
+    /// Like [`byte_to_char`](Self::byte_to_char), but returns `None` instead
+    /// of panicking if `byte` is past the end of the buffer or not on a char
+    /// boundary.
+    pub fn try_byte_to_char(&self, byte: usize) -> Option<usize> {
+        self.is_char_boundary(byte).then(|| self.byte_to_char(byte))
+    }
+
+    /// Returns the number of chars between the byte offsets `beg` and `end`.
+    /// Only the text in the range is counted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `beg` is greater than `end`, `end` is past the end of the
+    /// buffer, or either is not on a char boundary.
+    pub fn chars_in_byte_range(&self, beg: usize, end: usize) -> usize {
+        assert!(
+            beg <= end && end <= self.len(),
+            "byte range ({beg}-{end}) is out of bounds (len {})",
+            self.len()
+        );
+        assert!(
+            self.is_char_boundary(beg) && self.is_char_boundary(end),
+            "byte range ({beg}-{end}) is not on char boundaries"
+        );
+        let (before, after) = self.byte_range(beg, end);
+        chars::count(before) + chars::count(after)
+    }
+
+    /// Returns the column of the char position `pos`, counted in chars from
+    /// the start of its line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn char_to_column(&self, pos: usize) -> usize {
+        let byte = self.char_to_byte(pos);
+        let line_start = self.line_to_byte(self.byte_to_line(byte));
+        pos - self.byte_to_char(line_start)
+    }
+
+    /// Returns the display column of the char position `pos`. This is like
+    /// [`char_to_column`](Self::char_to_column), but a tab advances to the
+    /// next multiple of `tab_width`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer or `tab_width` is zero.
+    pub fn char_to_display_column(&self, pos: usize, tab_width: usize) -> usize {
+        assert!(tab_width > 0, "tab width must be greater than zero");
+        let byte = self.char_to_byte(pos);
+        let (before, after) = self.byte_range(self.line_start_byte(byte), byte);
+        before.chars().chain(after.chars()).fold(0, |column, chr| {
+            if chr == '\t' {
+                (column / tab_width + 1) * tab_width
+            } else {
+                column + 1
+            }
+        })
+    }
+
+    /// Converts a char position to a UTF-16 code unit offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn char_to_utf16(&self, pos: usize) -> usize {
+        let (before, after) = self.byte_range(0, self.char_to_byte(pos));
+        pos + utf16::count_surrogates(before) + utf16::count_surrogates(after)
+    }
+
+    /// Converts a UTF-16 code unit offset to a char position. An offset in
+    /// the middle of a surrogate pair returns the position of that char.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn utf16_to_char(&self, pos: usize) -> usize {
+        let before = self.to_str(..self.gap_start);
+        let before_len = utf16::count(before);
+        if pos <= before_len {
+            return chars::from_byte_idx(before, utf16::to_byte_idx(before, pos));
+        }
+        let after = self.to_str(self.gap_end..);
+        let after_len = utf16::count(after);
+        assert!(
+            pos - before_len <= after_len,
+            "utf16 offset ({pos}) is out of bounds (len {})",
+            before_len + after_len
+        );
+        let byte = utf16::to_byte_idx(after, pos - before_len);
+        self.gap_chars + chars::from_byte_idx(after, byte)
+    }
+
+    /// Check that the internal state of the buffer is consistent. Returns a
+    /// description of the first problem found.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the cached positions or counts do not match
+    /// the data.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.gap_start > self.gap_end || self.gap_end > self.data.len() {
+            return Err(format!(
+                "gap ({}-{}) out of bounds (capacity {})",
+                self.gap_start,
+                self.gap_end,
+                self.data.len()
+            ));
+        }
+        let before = std::str::from_utf8(&self.data[..self.gap_start])
+            .map_err(|e| format!("text before gap is not utf8: {e}"))?;
+        let after = std::str::from_utf8(&self.data[self.gap_end..])
+            .map_err(|e| format!("text after gap is not utf8: {e}"))?;
+        let gap_chars = chars::count(before);
+        if self.gap_chars != gap_chars {
+            return Err(format!(
+                "gap_chars ({}) does not match text ({gap_chars})",
+                self.gap_chars
+            ));
+        }
+        let total_chars = gap_chars + chars::count(after);
+        if self.total_chars != total_chars {
+            return Err(format!(
+                "total_chars ({}) does not match text ({total_chars})",
+                self.total_chars
+            ));
+        }
+        let total_lines = lines_lf::count_breaks(before) + lines_lf::count_breaks(after);
+        if self.total_lines != total_lines {
+            return Err(format!(
+                "total_lines ({}) does not match text ({total_lines})",
+                self.total_lines
+            ));
+        }
+        let Point { byte, char } = self.cursor;
+        if char > total_chars {
+            return Err(format!(
+                "cursor char ({char}) out of bounds ({total_chars})"
+            ));
+        }
+        let expected = if char < gap_chars {
+            chars::to_byte_idx(before, char)
+        } else {
+            self.gap_end + chars::to_byte_idx(after, char - gap_chars)
+        };
+        if byte != expected {
+            return Err(format!(
+                "cursor byte ({byte}) does not match char ({char}), expected {expected}"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the text between the byte offsets `beg` and `end`, split into
+    /// the parts before and after the gap.
+    fn byte_range(&self, beg: usize, end: usize) -> (&str, &str) {
+        if end <= self.gap_start {
+            (self.to_str(beg..end), "")
+        } else if beg >= self.gap_start {
+            (
+                "",
+                self.to_str(self.to_raw_byte(beg)..self.to_raw_byte(end)),
+            )
+        } else {
+            let before = self.to_str(beg..self.gap_start);
+            let after = self.to_str(self.gap_end..self.to_raw_byte(end));
+            (before, after)
+        }
+    }
+
+    /// Returns true if `byte` is the start of a char or the end of the buffer.
+    /// This mirrors [`str::is_char_boundary`].
+    #[inline]
+    pub fn is_char_boundary(&self, byte: usize) -> bool {
+        match byte.cmp(&self.len()) {
+            std::cmp::Ordering::Less => is_char_boundary(self.data[self.to_raw_byte(byte)]),
+            std::cmp::Ordering::Equal => true,
+            std::cmp::Ordering::Greater => false,
+        }
+    }
+
+    /// Converts a byte offset into an index into `data`.
+    #[inline]
+    const fn to_raw_byte(&self, byte: usize) -> usize {
+        if byte < self.gap_start {
+            byte
+        } else {
+            byte + self.gap_len()
+        }
+    }
+
+    /// Converts an index into `data` into a byte offset.
+    #[inline]
+    const fn to_logical_byte(&self, raw: usize) -> usize {
+        if raw < self.gap_end {
+            raw
+        } else {
+            raw - self.gap_len()
+        }
+    }
+
+    #[inline]
+    const fn gap_len(&self) -> usize {
+        self.gap_end - self.gap_start
+    }
+
+    fn char_to_raw_byte(&self, pos: usize) -> usize {
+        if pos == 0 {
+            return if self.gap_start == 0 { self.gap_end } else { 0 };
+        }
+        if pos == self.total_chars {
+            return self.data.len();
+        }
+        // (byte position, char positions) pairs sorted in ascending order
+        let positions = {
+            let start = (self.gap_start, self.gap_chars);
+            let end = (self.gap_end, self.gap_chars);
+            let total = (self.data.len(), self.total_chars);
+            let cursor = (self.cursor.byte, self.cursor.char);
+            if self.cursor.char < self.gap_chars {
+                [(0, 0), cursor, start, end, total]
+            } else {
+                [(0, 0), start, end, cursor, total]
+            }
+        };
+
+        // find which positions window the char position falls into
+        let window = positions
+            .windows(2)
+            .find(|slice| slice[0].1 <= pos && pos < slice[1].1);
+
+        let Some([(beg_byte, beg_char), (end_byte, end_char)]) = window else {
+            unreachable!("char position ({pos}) did not fall into any window");
+        };
+
+        self.assert_char_boundary(*beg_byte);
+        self.assert_char_boundary(*end_byte);
+
+        let num_chars = end_char - beg_char;
+        if end_byte - beg_byte == num_chars {
+            // the slice is ascii text, so we can just index into it
+            beg_byte + (pos - beg_char)
+        } else {
+            let string = self.to_str(*beg_byte..*end_byte);
+            let byte_idx = chars::to_byte_idx(string, pos - beg_char);
+            beg_byte + byte_idx
+        }
+    }
+
+    fn to_str(&self, range: impl std::slice::SliceIndex<[u8], Output = [u8]>) -> &str {
+        // TODO: remove this check once we are confident the code is correct
+        std::str::from_utf8(&self.data[range]).unwrap()
+    }
+
+    fn assert_char_boundary(&self, pos: usize) {
+        if pos == self.gap_start {
+            return;
+        }
+        let is_boundary = match self.data.get(pos) {
+            Some(byte) => is_char_boundary(*byte),
+            None => pos == self.data.len(),
+        };
+        assert!(is_boundary, "position ({pos}) not on utf8 boundary");
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+#[inline]
+const fn is_char_boundary(byte: u8) -> bool {
+    // This is bit magic equivalent to: b < 128 || b >= 192
+    (byte as i8) >= -0x40
+}
+
+#[inline]
+fn count_lines(bytes: &[u8]) -> usize {
+    bytecount::count(bytes, b'\n')
+}
+
+#[allow(dead_code)]
+#[cfg(test)]
+impl Buffer {
+    // used to convert indexes from the fuzzer
+    fn wrap(&self, pos: usize) -> usize {
+        let pos = pos % (self.len() + 2);
+        println!("pos: {:?}", pos);
+        pos
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn create() {
+        let string = "hello buffer";
+        let buffer = Buffer::from(string);
+        assert_eq!(buffer.data.len(), string.len() + Buffer::GAP_SIZE);
+        assert_eq!(buffer.gap_end, Buffer::GAP_SIZE);
+        assert_eq!(buffer.gap_start, 0);
+    }
+
+    #[test]
+    fn default() {
+        let buffer = Buffer::default();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.len_lines(), 1);
+        assert_eq!(buffer, Buffer::from(""));
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn from_string() {
+        let string = "hello Θ\nworld";
+        let mut owned = String::with_capacity(string.len() + Buffer::GAP_SIZE);
+        owned.push_str(string);
+        let ptr = owned.as_ptr();
+        let mut buffer = Buffer::from_string(owned);
+        assert_eq!(buffer.data.as_ptr(), ptr);
+        assert_eq!(buffer.gap(), (string.len(), Buffer::GAP_SIZE));
+        assert_eq!(buffer.to_string(), string);
+        assert_eq!(buffer.len_chars(), 13);
+        assert_eq!(buffer.len_lines(), 2);
+        assert_eq!(buffer.cursor.char, 13);
+        assert_eq!(buffer.validate(), Ok(()));
+        // the first insert appends into the gap without moving the text
+        buffer.insert("!");
+        assert_eq!(buffer.data.as_ptr(), ptr);
+        assert_eq!(buffer.gap(), (string.len() + 1, Buffer::GAP_SIZE - 1));
+        assert_eq!(buffer.to_string(), "hello Θ\nworld!");
+
+        // extra capacity is released
+        let mut owned = String::with_capacity(100);
+        owned.push_str(string);
+        let buffer = Buffer::from_string(owned);
+        assert_eq!(buffer.capacity(), string.len() + Buffer::GAP_SIZE);
+        assert_eq!(buffer.to_string(), string);
+        assert_eq!(buffer.validate(), Ok(()));
+
+        let buffer = Buffer::from_string(String::new());
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.validate(), Ok(()));
+
+        // From<String> keeps the gap and cursor at the front like From<&str>
+        let mut buffer = Buffer::from(String::from("world"));
+        buffer.insert("hi ");
+        assert_eq!(buffer.to_string(), "hi world");
+    }
+
+    #[test]
+    fn from_iter() {
+        let buffer: Buffer = "hello Θ world".chars().collect();
+        assert_eq!(buffer.to_string(), "hello Θ world");
+        let buffer: Buffer = ["hello", " ", "Θ", " world"].into_iter().collect();
+        assert_eq!(buffer.to_string(), "hello Θ world");
+        assert_eq!(buffer.len_chars(), 13);
+        let buffer: Buffer = vec![String::from("a\n"), String::from("b")]
+            .into_iter()
+            .collect();
+        assert_eq!(buffer.to_string(), "a\nb");
+        assert_eq!(buffer.len_lines(), 2);
+    }
+
+    #[test]
+    fn from_spans() {
+        let spans: Vec<String> = (0..100).map(|i| format!("span{i}Θ\n")).collect();
+        let buffer: Buffer = spans.iter().map(String::as_str).collect();
+        let concat = spans.concat();
+        assert_eq!(buffer, Buffer::from(concat.as_str()));
+        assert_eq!(buffer.len_lines(), 101);
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn try_from_bytes() {
+        let buffer = Buffer::try_from_bytes("hello Θ\n".as_bytes()).unwrap();
+        assert_eq!(buffer.to_string(), "hello Θ\n");
+        assert_eq!(buffer.len_chars(), 8);
+        assert_eq!(buffer.validate(), Ok(()));
+        let err = Buffer::try_from_bytes(b"abc\xCE\x98\xCEd").unwrap_err();
+        assert_eq!(err.valid_up_to(), 5);
+        // an encoded surrogate is not valid utf8
+        let err = Buffer::try_from_bytes(b"a\xED\xA0\x80").unwrap_err();
+        assert_eq!(err.valid_up_to(), 1);
+    }
+
+    #[test]
+    fn from_reader() {
+        let string = "hello Θ\nworld ƽ";
+        // use a tiny buffer so that multi-byte chars are split across reads
+        let reader = std::io::BufReader::with_capacity(2, string.as_bytes());
+        let buffer = Buffer::from_reader(reader).unwrap();
+        assert_eq!(buffer.to_string(), string);
+        assert_eq!(buffer.len_chars(), 15);
+        assert_eq!(buffer.len_lines(), 2);
+        assert_eq!(buffer.validate(), Ok(()));
+
+        let buffer = Buffer::from_reader(std::io::Cursor::new("")).unwrap();
+        assert!(buffer.is_empty());
+        let err = Buffer::from_reader(&[b'a', 0xCE][..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn write_to() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        let mut output = Vec::new();
+        buffer.write_to(&mut output).unwrap();
+        assert_eq!(output, "hello Θ world".as_bytes());
+    }
+
+    #[test]
+    fn insert_at_byte() {
+        let mut buffer = Buffer::from("Θƽ world");
+        buffer.insert_at_byte(2, "-");
+        assert_eq!(buffer.to_string(), "Θ-ƽ world");
+        buffer.insert_at_byte(buffer.len(), "!");
+        buffer.insert_at_byte(0, "> ");
+        assert_eq!(buffer.to_string(), "> Θ-ƽ world!");
+        assert_eq!(buffer.validate(), Ok(()));
+        // the cursor is after the inserted text, on either side of the old gap
+        buffer.move_gap(4);
+        buffer.insert_at_byte(10, "Ι");
+        assert_eq!(buffer.cursor.char, 9);
+        buffer.insert_at_byte(2, "ƽ");
+        assert_eq!(buffer.cursor.char, 3);
+        assert_eq!(buffer.to_string(), "> ƽΘ-ƽ woΙrld!");
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "not on a char boundary")]
+    fn insert_at_byte_mid_char() {
+        let mut buffer = Buffer::from("Θƽ");
+        buffer.insert_at_byte(3, "-");
+    }
+
+    #[test]
+    fn insert_buffer() {
+        let mut buffer = Buffer::from("hello\nworld");
+        let mut other = Buffer::from("Θ\nbig ");
+        other.insert("a ");
+        buffer.insert_buffer(6, &other);
+        assert_eq!(buffer.to_string(), "hello\na Θ\nbig world");
+        assert_eq!(buffer.len_chars(), 19);
+        assert_eq!(buffer.len_lines(), 3);
+        assert_eq!(buffer.cursor.char, 14);
+        assert_eq!(buffer.validate(), Ok(()));
+        assert_eq!(other.to_string(), "a Θ\nbig ");
+        buffer.insert_buffer(0, &Buffer::new());
+        assert_eq!(buffer.to_string(), "hello\na Θ\nbig world");
+    }
+
+    #[test]
+    fn truncate() {
+        let mut buffer = Buffer::from("Θ world\n");
+        buffer.insert("hello ");
+        buffer.truncate(8);
+        assert_eq!(buffer.to_string(), "hello Θ ");
+        assert_eq!(buffer.len_lines(), 1);
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.truncate(20);
+        assert_eq!(buffer.to_string(), "hello Θ ");
+        buffer.truncate(0);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn remove_char() {
+        let mut buffer = Buffer::from("Θ\nworld");
+        buffer.insert("hello ");
+        assert_eq!(buffer.remove_char(6), Some('Θ'));
+        assert_eq!(buffer.to_string(), "hello \nworld");
+        assert_eq!(buffer.len_lines(), 2);
+        assert_eq!(buffer.remove_char(6), Some('\n'));
+        assert_eq!(buffer.len_lines(), 1);
+        assert_eq!(buffer.len_chars(), 11);
+        assert_eq!(buffer.remove_char(11), None);
+        assert_eq!(buffer.to_string(), "hello world");
+        assert_eq!(buffer.validate(), Ok(()));
+        assert_eq!(Buffer::new().remove_char(0), None);
+    }
+
+    #[test]
+    fn retain() {
+        let mut buffer = Buffer::from("Θ 2 world\n42");
+        buffer.insert("h1ello ");
+        buffer.retain(|chr| !chr.is_ascii_digit());
+        assert_eq!(buffer.to_string(), "hello Θ  world\n");
+        assert_eq!(buffer.len_chars(), 15);
+        assert_eq!(buffer.len_lines(), 2);
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.retain(|_| false);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn trim_matches() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("  hello ");
+        buffer.push_str("   \n\n");
+        buffer.trim_end_matches(' ');
+        assert_eq!(buffer.to_string(), "  hello Θ world   \n\n");
+        buffer.trim_end_matches('\n');
+        assert_eq!(buffer.len_lines(), 1);
+        buffer.trim_end_matches(' ');
+        assert_eq!(buffer.to_string(), "  hello Θ world");
+        buffer.trim_start_matches(' ');
+        assert_eq!(buffer.to_string(), "hello Θ world");
+        assert_eq!(buffer.len_chars(), 13);
+        assert_eq!(buffer.validate(), Ok(()));
+
+        let mut buffer = Buffer::from("ƽƽƽ");
+        buffer.trim_start_matches('ƽ');
+        assert!(buffer.is_empty());
+        buffer.trim_end_matches('ƽ');
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn clear() {
+        let mut buffer = Buffer::from("Θ world\n");
+        buffer.insert("hello ");
+        let capacity = buffer.data.len();
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.len_lines(), 1);
+        assert_eq!(buffer.data.len(), capacity);
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.insert("new\ntext");
+        assert_eq!(buffer.to_string(), "new\ntext");
+        assert_eq!(buffer.data.len(), capacity);
+        assert_eq!(buffer.len_lines(), 2);
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn extend() {
+        let mut buffer = Buffer::from("Θ");
+        buffer.extend(vec![" hello", "\n", "world"]);
+        assert_eq!(buffer.to_string(), "Θ hello\nworld");
+        buffer.extend(vec![String::from(" ƽ")]);
+        buffer.extend("!?".chars());
+        assert_eq!(buffer.to_string(), "Θ hello\nworld ƽ!?");
+        assert_eq!(buffer.len_lines(), 2);
+        assert_eq!(buffer.cursor.char, 0);
+        assert_eq!(buffer.validate(), Ok(()));
+
+        // the gap is grown once for all the items and the cursor stays put
+        buffer.set_cursor(3);
+        let len = buffer.len();
+        buffer.extend(std::iter::repeat_n("ƽ", 20));
+        assert_eq!(buffer.len(), len + 40);
+        assert_eq!(buffer.gap(), (buffer.len(), Buffer::GAP_SIZE));
+        assert_eq!(buffer.cursor.char, 3);
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut buffer = Buffer::from("world ƽ");
+        buffer.insert("hello Θ\n");
+        let chunks: Vec<String> = buffer.into_iter().collect();
+        assert_eq!(chunks.concat(), "hello Θ\nworld ƽ");
+        assert_eq!(Buffer::new().into_iter().count(), 0);
+    }
+
+    #[test]
+    fn char_to_line() {
+        let mut buffer = Buffer::from("Θ\nƽ world\n\nend");
+        buffer.insert("hello ");
+        let string = buffer.to_string();
+        for pos in 0..=buffer.len_chars() {
+            let byte = buffer.char_to_byte(pos);
+            assert_eq!(buffer.char_to_line(pos), buffer.byte_to_line(byte));
+            assert_eq!(
+                buffer.char_to_line(pos),
+                string[..byte].matches('\n').count()
+            );
+        }
+        assert_eq!(buffer.char_to_line(7), 0);
+        assert_eq!(buffer.char_to_line(8), 1);
+        assert_eq!(buffer.char_to_line(buffer.len_chars()), 3);
+    }
+
+    #[test]
+    fn line_to_char() {
+        let mut buffer = Buffer::from("Θ\nƽ world\n\nend");
+        buffer.insert("hello ");
+        assert_eq!(buffer.line_to_char(0), 0);
+        assert_eq!(buffer.line_to_char(1), 8);
+        assert_eq!(buffer.line_to_char(2), 16);
+        assert_eq!(buffer.line_to_char(3), 17);
+        assert_eq!(buffer.line_to_char(buffer.len_lines()), buffer.len_chars());
+        for line in 0..buffer.len_lines() {
+            assert_eq!(buffer.char_to_line(buffer.line_to_char(line)), line);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn char_to_line_out_of_bounds() {
+        Buffer::from("a\nb").char_to_line(4);
+    }
+
+    #[test]
+    fn line_len() {
+        let mut buffer = Buffer::from("Θ\nworld ƽ\nend");
+        buffer.insert("hello ");
+        assert_eq!(buffer.bytes_in_line(0), 8);
+        assert_eq!(buffer.chars_in_line(0), 7);
+        assert_eq!(buffer.bytes_in_line(1), 8);
+        assert_eq!(buffer.chars_in_line(1), 7);
+        assert_eq!(buffer.bytes_in_line(2), 3);
+        assert_eq!(buffer.chars_in_line(2), 3);
+        buffer.push_str("\n");
+        assert_eq!(buffer.bytes_in_line(3), 0);
+        assert_eq!(buffer.chars_in_line(3), 0);
+        assert_eq!(Buffer::new().bytes_in_line(0), 0);
+    }
+
+    #[test]
+    fn line_start_end() {
+        let mut buffer = Buffer::from("Θ\nworld\r\n");
+        buffer.insert("hello ");
+        assert_eq!(buffer.line_start_byte(0), 0);
+        assert_eq!(buffer.line_end_byte(0), 8);
+        assert_eq!(buffer.line_start_byte(6), 0);
+        assert_eq!(buffer.line_end_byte(6), 8);
+        // the newline belongs to the line it ends
+        assert_eq!(buffer.line_start_byte(8), 0);
+        assert_eq!(buffer.line_start_byte(9), 9);
+        assert_eq!(buffer.line_end_byte(9), 14);
+        assert_eq!(buffer.line_start_byte(16), 16);
+        assert_eq!(buffer.line_end_byte(16), 16);
+    }
+
+    #[test]
+    fn line_len_crlf() {
+        let mut buffer = Buffer::from("b\r\nΘ\n\r\nend\r");
+        buffer.insert("a");
+        assert_eq!(buffer.len_lines(), 4);
+        assert_eq!(buffer.bytes_in_line(0), 2);
+        assert_eq!(buffer.chars_in_line(0), 2);
+        assert_eq!(buffer.line_to_byte(1), 4);
+        assert_eq!(buffer.bytes_in_line(1), 2);
+        assert_eq!(buffer.chars_in_line(1), 1);
+        assert_eq!(buffer.bytes_in_line(2), 0);
+        // a lone carriage return on the last line is part of the text
+        assert_eq!(buffer.bytes_in_line(3), 4);
+        // crlf split across the gap
+        buffer.move_gap(3);
+        assert_eq!(buffer.bytes_in_line(0), 2);
+        assert_eq!(buffer.chars_in_line(0), 2);
+        assert_eq!(buffer.line_end_byte(0), 2);
+
+        // in lf mode the carriage returns are part of the lines
+        buffer.set_line_ending(LineEnding::Lf);
+        assert_eq!(buffer.len_lines(), 4);
+        assert_eq!(buffer.bytes_in_line(0), 3);
+        assert_eq!(buffer.chars_in_line(0), 3);
+        assert_eq!(buffer.line_end_byte(0), 3);
+        assert_eq!(buffer.line_to_byte(1), 4);
+        assert_eq!(buffer.bytes_in_line(1), 2);
+        assert_eq!(buffer.chars_in_line(1), 1);
+        assert_eq!(buffer.bytes_in_line(2), 1);
+        assert_eq!(buffer.line_to_byte(3), 9);
+        assert_eq!(buffer.bytes_in_line(3), 4);
+        buffer.move_gap(0);
+        assert_eq!(buffer.bytes_in_line(0), 3);
+    }
+
+    #[test]
+    fn line_ending() {
+        let mut buffer = Buffer::from("a\r\nb");
+        assert_eq!(buffer.line_ending(), LineEnding::CrLf);
+        buffer.set_line_ending(LineEnding::Lf);
+        assert_eq!(buffer.to_string(), "a\r\nb");
+        assert_eq!(buffer.slice(0, 3).line_ending(), LineEnding::Lf);
+        assert_eq!(buffer.split_off(1).line_ending(), LineEnding::Lf);
+        let mut empty = Buffer::new();
+        empty.set_line_ending(LineEnding::Lf);
+        empty.append(Buffer::from("a\r\n"));
+        assert_eq!(empty.line_ending(), LineEnding::Lf);
+        assert_eq!(empty.bytes_in_line(0), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn line_len_out_of_bounds() {
+        Buffer::from("a\nb").bytes_in_line(2);
+    }
+
+    #[test]
+    fn slice_str() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        assert!(matches!(buffer.slice_str(0, 5), Cow::Borrowed("hello")));
+        assert!(matches!(buffer.slice_str(6, 9), Cow::Borrowed("Θ w")));
+        assert!(matches!(buffer.slice_str(6, 6), Cow::Borrowed("")));
+        let slice = buffer.slice_str(4, 8);
+        assert!(matches!(slice, Cow::Owned(_)));
+        assert_eq!(slice, "o Θ ");
+        assert_eq!(buffer.slice_str(0, 13), "hello Θ world");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn slice_str_out_of_bounds() {
+        let _ = Buffer::from("abc").slice_str(2, 4);
+    }
+
+    #[test]
+    fn as_contiguous() {
+        let mut buffer = Buffer::from("Θ world");
+        assert_eq!(buffer.as_contiguous(), Some("Θ world"));
+        buffer.insert("hello ");
+        assert_eq!(buffer.as_contiguous(), None);
+        buffer.move_gap(buffer.len_chars());
+        assert_eq!(buffer.as_contiguous(), Some("hello Θ world"));
+        assert_eq!(Buffer::new().as_contiguous(), Some(""));
+    }
+
+    #[test]
+    fn chunk_indices() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        let chunks: Vec<_> = buffer.chunk_indices().collect();
+        assert_eq!(chunks, [(0, "hello "), (6, "Θ world")]);
+        assert!(chunks.windows(2).all(|w| w[0].0 < w[1].0));
+        let (start, last) = chunks.last().unwrap();
+        assert_eq!(start + last.len(), buffer.len());
+
+        buffer.move_gap(0);
+        assert_eq!(
+            buffer.chunk_indices().collect::<Vec<_>>(),
+            [(0, "hello Θ world")]
+        );
+        assert_eq!(Buffer::new().chunk_indices().count(), 0);
+    }
+
+    #[test]
+    fn chunk_at_byte() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        let string = buffer.to_string();
+        for byte in 0..buffer.len() {
+            let (chunk, start) = buffer.chunk_at_byte(byte);
+            assert!(start <= byte && byte < start + chunk.len());
+            assert_eq!(&string[start..start + chunk.len()], chunk);
+        }
+        assert_eq!(buffer.chunk_at_byte(0), ("hello ", 0));
+        assert_eq!(buffer.chunk_at_byte(6), ("Θ world", 6));
+        assert_eq!(buffer.chunk_at_byte(buffer.len()), ("Θ world", 6));
+        assert_eq!(Buffer::new().chunk_at_byte(0), ("", 0));
+    }
+
+    #[test]
+    fn try_conversions() {
+        let mut buffer = Buffer::from("Θ\nworld");
+        buffer.insert("hello ");
+        assert_eq!(buffer.try_char_to_byte(7), Some(8));
+        assert_eq!(buffer.try_char_to_byte(13), Some(14));
+        assert_eq!(buffer.try_char_to_byte(14), None);
+        assert_eq!(buffer.try_byte_to_char(8), Some(7));
+        assert_eq!(buffer.try_byte_to_char(7), None);
+        assert_eq!(buffer.try_byte_to_char(15), None);
+        assert_eq!(buffer.try_line_to_byte(1), Some(9));
+        assert_eq!(buffer.try_line_to_byte(3), None);
+        assert_eq!(buffer.try_byte_to_line(14), Some(1));
+        assert_eq!(buffer.try_byte_to_line(15), None);
+        assert_eq!(buffer.try_insert_str(14, "!"), None);
+        assert_eq!(buffer.to_string(), "hello Θ\nworld");
+        assert!(buffer.try_insert_str(13, "!").is_some());
+        assert_eq!(buffer.to_string(), "hello Θ\nworld!");
+    }
+
+    #[test]
+    fn chars_in_byte_range() {
+        let mut buffer = Buffer::from("Θ world ƽ");
+        buffer.insert("hello ");
+        let string = buffer.to_string();
+        let bounds: Vec<_> = (0..=string.len())
+            .filter(|&b| string.is_char_boundary(b))
+            .collect();
+        for &beg in &bounds {
+            for &end in bounds.iter().filter(|&&end| end >= beg) {
+                let naive = string
+                    .chars()
+                    .skip(string[..beg].chars().count())
+                    .take(string[beg..end].chars().count())
+                    .count();
+                assert_eq!(buffer.chars_in_byte_range(beg, end), naive);
+            }
+        }
+        assert_eq!(buffer.chars_in_byte_range(0, buffer.len()), 15);
+    }
+
+    #[test]
+    #[should_panic(expected = "char boundaries")]
+    fn chars_in_byte_range_boundary() {
+        Buffer::from("Θ").chars_in_byte_range(0, 1);
+    }
+
+    #[test]
+    fn shrink_gap() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.set_cursor(7);
+        buffer.reserve_gap(100);
+        buffer.insert_str(1, "hi");
+        assert_eq!(buffer.gap_len(), 98);
+        buffer.shrink_gap();
+        assert_eq!(buffer.gap_len(), Buffer::GAP_SIZE);
+        assert_eq!(buffer.data.len(), buffer.len() + Buffer::GAP_SIZE);
+        assert_eq!(buffer.to_string(), "Θhi world");
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.insert("!");
+        assert_eq!(buffer.to_string(), "Θhi! world");
+
+        // a small gap is left alone
+        let mut buffer = Buffer::from("abc");
+        buffer.insert("x");
+        buffer.shrink_gap();
+        assert_eq!(buffer.gap_len(), Buffer::GAP_SIZE - 1);
+    }
+
+    #[test]
+    fn capacity() {
+        let mut buffer = Buffer::from("hello");
+        assert_eq!(buffer.capacity(), 5 + Buffer::GAP_SIZE);
+        let capacity = buffer.capacity();
+        buffer.insert(&"Θ".repeat(100));
+        assert!(buffer.capacity() > capacity);
+        assert_eq!(buffer.capacity(), buffer.len() + buffer.gap().1);
+        buffer.clear();
+        assert!(buffer.capacity() > capacity);
+    }
+
+    #[test]
+    fn move_gap() {
+        let mut buffer = Buffer::from("hello Θ world");
+        buffer.move_gap(7);
+        assert_eq!(buffer.gap_chars, 7);
+        assert_eq!(buffer.gap_start, 8);
+        assert_eq!(buffer.to_string(), "hello Θ world");
+        assert_eq!(buffer.len_chars(), 13);
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.insert_str(7, "!");
+        assert_eq!(buffer.gap_start, 9);
+        assert_eq!(buffer.to_string(), "hello Θ! world");
+        buffer.move_gap(0);
+        buffer.delete_region(0, 1);
+        assert_eq!(buffer.to_string(), "ello Θ! world");
+        buffer.move_gap(13);
+        assert_eq!(buffer.gap_end, buffer.data.len());
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn gap() {
+        let mut buffer = Buffer::from("Θ world");
+        assert_eq!(buffer.gap(), (0, Buffer::GAP_SIZE));
+        buffer.insert("hi");
+        assert_eq!(buffer.gap(), (2, Buffer::GAP_SIZE - 2));
+        buffer.move_gap(3);
+        assert_eq!(buffer.gap(), (4, Buffer::GAP_SIZE - 2));
+        assert_eq!(buffer.len(), buffer.data.len() - buffer.gap().1);
+        assert_eq!(buffer.len_chars(), 9);
+    }
+
+    #[test]
+    fn reserve_gap() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.set_cursor(4);
+        buffer.reserve_gap(20);
+        assert_eq!(buffer.gap_len(), 20);
+        assert_eq!(buffer.to_string(), "Θ world");
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.set_cursor(0);
+        buffer.insert("hello there ");
+        buffer.insert("big ");
+        assert_eq!(buffer.gap_len(), 4);
+        assert_eq!(buffer.data.len(), 20 + 8);
+        assert_eq!(buffer.to_string(), "hello there big Θ world");
+        assert_eq!(buffer.len_chars(), 23);
+        // already large enough
+        buffer.reserve_gap(2);
+        assert_eq!(buffer.gap_len(), 4);
+        assert_eq!(buffer.validate(), Ok(()));
     }
 
     #[test]
@@ -500,6 +2370,504 @@ mod test {
         assert_eq!(buffer.to_string(), "");
     }
 
+    #[test]
+    fn lines() {
+        let mut buffer = Buffer::from("hello\nbuffer\n");
+        assert_eq!(buffer.total_lines, 2);
+        buffer.insert("one\ntwo\n");
+        assert_eq!(buffer.total_lines, 4);
+        buffer.insert("\n");
+        assert_eq!(buffer.total_lines, 5);
+        buffer.delete_region(6, 14);
+        assert_eq!(buffer.to_string(), "one\ntw\nbuffer\n");
+        assert_eq!(buffer.total_lines, 3);
+        buffer.delete_region(0, 100);
+        assert_eq!(buffer.total_lines, 0);
+    }
+
+    #[test]
+    fn line_conversions() {
+        let mut buffer = Buffer::from("Θne\ntwo\nthree");
+        buffer.set_cursor(6);
+        buffer.insert("x\n");
+        assert_eq!(buffer.to_string(), "Θne\ntwx\no\nthree");
+        assert_eq!(buffer.line_to_byte(0), 0);
+        assert_eq!(buffer.line_to_byte(1), 5);
+        assert_eq!(buffer.line_to_byte(2), 9);
+        assert_eq!(buffer.line_to_byte(3), 11);
+        assert_eq!(buffer.line_to_byte(4), buffer.len());
+        assert_eq!(buffer.byte_to_line(0), 0);
+        assert_eq!(buffer.byte_to_line(4), 0);
+        assert_eq!(buffer.byte_to_line(5), 1);
+        assert_eq!(buffer.byte_to_line(10), 2);
+        assert_eq!(buffer.byte_to_line(13), 3);
+        assert_eq!(buffer.byte_to_line(buffer.len()), 3);
+    }
+
+    #[test]
+    fn chunks() {
+        let mut buffer = Buffer::from("world");
+        assert_eq!(buffer.chunks().collect::<Vec<_>>(), ["world"]);
+        buffer.insert("hello ");
+        assert_eq!(buffer.chunks().collect::<Vec<_>>(), ["hello ", "world"]);
+        assert_eq!(buffer.chunks().collect::<String>(), buffer.to_string());
+        assert_eq!(Buffer::new().chunks().count(), 0);
+        assert_eq!(Buffer::from("").chunks().count(), 0);
+    }
+
+    #[test]
+    fn chars() {
+        let string = "hello Θ world ƽ";
+        let mut buffer = Buffer::from("Θ world ƽ");
+        buffer.insert("hello ");
+        assert_eq!(buffer.chars().count(), buffer.len_chars());
+        assert!(buffer.chars().eq(string.chars()));
+    }
+
+    #[test]
+    fn into_line_buffers() {
+        let mut buffer = Buffer::from("Θ\nbig\nworld");
+        buffer.insert("hello ");
+        let lines = buffer.clone().into_line_buffers();
+        assert_eq!(lines, ["hello Θ", "big", "world"]);
+        assert!(lines.iter().all(|line| line.validate().is_ok()));
+        buffer.push_str("\n");
+        assert_eq!(buffer.into_line_buffers(), ["hello Θ", "big", "world"]);
+        assert!(Buffer::new().into_line_buffers().is_empty());
+        let lines = Buffer::from("a\r\nb\r").into_line_buffers();
+        assert_eq!(lines, ["a", "b\r"]);
+    }
+
+    #[test]
+    fn chars_rev() {
+        let mut buffer = Buffer::from("Θ world ƽ");
+        buffer.insert("hello ");
+        let mut forward: Vec<_> = buffer.chars().collect();
+        forward.reverse();
+        assert_eq!(buffer.chars().rev().collect::<Vec<_>>(), forward);
+        assert!(buffer.bytes().rev().eq(buffer.to_string().bytes().rev()));
+        assert_eq!(
+            buffer.chunks().rev().collect::<Vec<_>>(),
+            ["Θ world ƽ", "hello "]
+        );
+    }
+
+    #[test]
+    fn char_indices() {
+        let mut buffer = Buffer::from("Θ wƽrld");
+        buffer.insert("hello ");
+        let indices: Vec<_> = buffer.char_indices().collect();
+        assert_eq!(indices[6], (6, 'Θ'));
+        assert_eq!(indices[7], (7, ' '));
+        assert!(indices.windows(2).all(|w| w[0].0 + 1 == w[1].0));
+        assert_eq!(indices.last().unwrap().0 + 1, buffer.len_chars());
+    }
+
+    #[test]
+    fn bytes() {
+        let string = "hello Θ world";
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        assert_eq!(buffer.bytes().count(), buffer.len());
+        assert!(buffer.bytes().eq(string.bytes()));
+    }
+
+    #[test]
+    fn lines_iter() {
+        let mut buffer = Buffer::from("o\nthree\n");
+        buffer.insert("one\ntw");
+        assert_eq!(buffer.lines().collect::<Vec<_>>(), ["one", "two", "three"]);
+        buffer.delete_region(13, 14);
+        assert_eq!(buffer.lines().collect::<Vec<_>>(), ["one", "two", "three"]);
+        buffer.insert_str(0, "\r\n");
+        assert_eq!(
+            buffer.lines().collect::<Vec<_>>(),
+            ["", "one", "two", "three"]
+        );
+        assert_eq!(Buffer::new().lines().count(), 0);
+        // a lone \r at the end is not a line ending
+        let buffer = Buffer::from("a\nb\r");
+        assert_eq!(buffer.lines().collect::<Vec<_>>(), ["a", "b\r"]);
+        assert_eq!(buffer.bytes_in_line(1), 2);
+        assert_eq!(Buffer::from("a\r\r\n").lines().collect::<Vec<_>>(), ["a\r"]);
+    }
+
+    #[test]
+    fn len() {
+        let buffer = Buffer::new();
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.len_chars(), 0);
+        assert_eq!(buffer.len_lines(), 1);
+
+        let mut buffer = Buffer::from("Θ\nworld");
+        buffer.insert("hello\n");
+        assert_eq!(buffer.gap_len(), Buffer::GAP_SIZE);
+        assert_eq!(buffer.len(), 14);
+        assert_eq!(buffer.len_chars(), 13);
+        assert_eq!(buffer.len_lines(), 3);
+    }
+
+    #[test]
+    fn char_byte_conversions() {
+        let mut buffer = Buffer::from("Θ wƽrld");
+        buffer.insert("hΘllo ");
+        assert_eq!(buffer.to_string(), "hΘllo Θ wƽrld");
+        let expected = [0, 1, 3, 4, 5, 6, 7, 9, 10, 11, 13, 14, 15, 16];
+        for (pos, byte) in expected.into_iter().enumerate() {
+            assert_eq!(buffer.char_to_byte(pos), byte);
+            assert_eq!(buffer.byte_to_char(byte), pos);
+        }
+    }
+
+    #[test]
+    fn char_boundary() {
+        let string = "hΘ ƽ!";
+        let mut buffer = Buffer::from("ƽ!");
+        buffer.insert("hΘ ");
+        for byte in 0..=string.len() + 1 {
+            assert_eq!(buffer.is_char_boundary(byte), string.is_char_boundary(byte));
+        }
+        assert!(buffer.is_char_boundary(0));
+        assert!(!buffer.is_char_boundary(2));
+        assert!(buffer.is_char_boundary(4));
+        assert!(!buffer.is_char_boundary(5));
+        assert!(buffer.is_char_boundary(buffer.len()));
+        assert!(Buffer::new().is_char_boundary(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "not on utf8 boundary")]
+    fn byte_to_char_mid_char() {
+        let buffer = Buffer::from("Θ");
+        buffer.byte_to_char(1);
+    }
+
+    #[test]
+    fn validate() {
+        let mut buffer = Buffer::from("hello Θ world");
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.insert_str(7, "new\n");
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.delete_region(2, 9);
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.set_cursor(3);
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.delete_backwards(2);
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.move_gap_out_of(..);
+        assert_eq!(buffer.validate(), Ok(()));
+
+        buffer.total_lines += 1;
+        assert!(buffer.validate().unwrap_err().contains("total_lines"));
+        buffer.total_lines -= 1;
+        buffer.cursor.byte += 1;
+        assert!(buffer.validate().unwrap_err().contains("cursor byte"));
+    }
+
+    #[test]
+    fn equal_str() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        assert!(buffer.eq_str("hello Θ world"));
+        assert_eq!(buffer, "hello Θ world");
+        assert_eq!(buffer, *"hello Θ world");
+        assert!(!buffer.eq_str("hello Θ worl"));
+        assert!(!buffer.eq_str("hello Θ world!"));
+        // same length, differs in the second byte of a multi-byte char
+        assert!(!buffer.eq_str("hello Ι world"));
+        assert_ne!(buffer, "hello ƽ world");
+        assert!(Buffer::new().eq_str(""));
+    }
+
+    #[test]
+    fn equal() {
+        let mut a = Buffer::from("world");
+        a.insert("hello ");
+        let mut b = Buffer::from("hello world");
+        b.move_gap(8);
+        assert_ne!(a.gap_start, b.gap_start);
+        assert_eq!(a, b);
+        b.delete_region(1, 2);
+        b.insert_str(1, "a");
+        assert_ne!(a, b);
+        b.delete_region(10, 11);
+        assert_ne!(a, b);
+        assert_eq!(Buffer::new(), Buffer::from(""));
+    }
+
+    #[test]
+    fn hash() {
+        use std::collections::HashSet;
+        let text = "hello Θ world ".repeat(30);
+        let mut set = HashSet::new();
+        for gap in [0, 10, 255, 256, 300] {
+            let mut buffer = Buffer::from(text.as_str());
+            buffer.move_gap(gap);
+            set.insert(buffer);
+        }
+        assert_eq!(set.len(), 1);
+        set.insert(Buffer::from("hello"));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn clone() {
+        let mut buffer = Buffer::from("world");
+        buffer.insert("hello ");
+        let copy = buffer.clone();
+        buffer.insert("there ");
+        assert_eq!(buffer.to_string(), "hello there world");
+        assert_eq!(copy.to_string(), "hello world");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        let json = serde_json::to_string(&buffer).unwrap();
+        assert_eq!(json, r#"{"text":"hello Θ world","gap":6}"#);
+        let mut new: Buffer = serde_json::from_str(&json).unwrap();
+        assert_eq!(new, buffer);
+        assert_eq!(new.gap_chars, 6);
+        assert_eq!(new.validate(), Ok(()));
+        // the insertion point survives the round-trip
+        buffer.insert("X");
+        new.insert("X");
+        assert_eq!(new, buffer);
+        assert_eq!(new.to_string(), "hello XΘ world");
+        assert_eq!(new.validate(), Ok(()));
+
+        let err = serde_json::from_str::<Buffer>(r#"{"text":"hi","gap":3}"#);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn char_at() {
+        let mut buffer = Buffer::from("Θ worlƽ");
+        buffer.insert("hello ");
+        assert_eq!(buffer.char_at(0), Some('h'));
+        assert_eq!(buffer.char_at(5), Some(' '));
+        assert_eq!(buffer.char_at(6), Some('Θ'));
+        assert_eq!(buffer.char_at(12), Some('ƽ'));
+        assert_eq!(buffer.char_at(13), None);
+        assert_eq!(Buffer::new().char_at(0), None);
+    }
+
+    #[test]
+    fn slice() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        assert_eq!(buffer.slice(0, 13).to_string(), "hello Θ world");
+        assert_eq!(buffer.slice(4, 9).to_string(), "o Θ w");
+        assert_eq!(buffer.slice(1, 3).to_string(), "el");
+        assert_eq!(buffer.slice(7, 13).to_string(), " world");
+        assert_eq!(buffer.slice(6, 6).to_string(), "");
+        assert!(buffer.slice(13, 13).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn slice_out_of_bounds() {
+        let buffer = Buffer::from("hello");
+        let _ = buffer.slice(2, 6);
+    }
+
+    #[test]
+    fn append() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        let mut other = Buffer::from("buffer\n");
+        other.insert(" and Θ ");
+        buffer.append(other);
+        assert_eq!(buffer.to_string(), "hello Θ world and Θ buffer\n");
+        assert_eq!(buffer.len_chars(), 27);
+        assert_eq!(buffer.len_lines(), 2);
+        assert_eq!(buffer.cursor.char, 6);
+        assert_eq!(buffer.validate(), Ok(()));
+        buffer.append(Buffer::new());
+        assert_eq!(buffer.to_string(), "hello Θ world and Θ buffer\n");
+
+        // an empty buffer takes over the storage of other
+        let mut buffer = Buffer::new();
+        let mut other = Buffer::from("world\n");
+        other.insert("hello Θ ");
+        let ptr = other.data.as_ptr();
+        buffer.append(other);
+        assert_eq!(buffer.data.as_ptr(), ptr);
+        assert_eq!(buffer.to_string(), "hello Θ world\n");
+        assert_eq!(buffer.cursor.char, 0);
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn push_str() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        let mut expected = buffer.clone();
+        buffer.push_str("!\nbye");
+        expected.insert_str(expected.len_chars(), "!\nbye");
+        assert_eq!(buffer, expected);
+        assert_eq!(buffer.to_string(), "hello Θ world!\nbye");
+        assert_eq!(buffer.len_lines(), 2);
+        assert_eq!(buffer.cursor.char, 6);
+        assert_eq!(buffer.validate(), Ok(()));
+
+        let mut buffer = Buffer::new();
+        buffer.push_str("a");
+        buffer.push_str("b");
+        assert_eq!(buffer.to_string(), "ab");
+        assert_eq!(buffer.cursor.char, 0);
+        assert_eq!(buffer.validate(), Ok(()));
+
+        // cursor after the gap, at the end, and on a growing buffer
+        let mut buffer = Buffer::from("Θ world");
+        for cursor in [3, 9, 11, 0, 12] {
+            buffer.set_cursor(cursor);
+            buffer.move_gap(1);
+            buffer.push_str("ƽ\n");
+            assert_eq!(buffer.cursor.char, cursor);
+            assert_eq!(buffer.validate(), Ok(()));
+        }
+        buffer.push_str("");
+        assert_eq!(buffer.to_string(), "Θ worldƽ\nƽ\nƽ\nƽ\nƽ\n");
+        assert_eq!(buffer.len_lines(), 6);
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn split_off() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        let original = buffer.clone();
+        let mut tail = buffer.split_off(7);
+        assert_eq!(buffer.to_string(), "hello Θ");
+        assert_eq!(tail.to_string(), " world");
+        assert_eq!(buffer.validate(), Ok(()));
+        assert_eq!(tail.validate(), Ok(()));
+        buffer.append(tail.clone());
+        assert_eq!(buffer, original);
+
+        assert!(tail.split_off(6).is_empty());
+        assert_eq!(tail.split_off(0).to_string(), " world");
+        assert!(tail.is_empty());
+    }
+
+    #[test]
+    fn column() {
+        let mut buffer = Buffer::from("ƽne\ntwΘ\nthree");
+        buffer.set_cursor(6);
+        buffer.insert("x");
+        assert_eq!(buffer.to_string(), "ƽne\ntwxΘ\nthree");
+        assert_eq!(buffer.char_to_column(0), 0);
+        assert_eq!(buffer.char_to_column(2), 2);
+        assert_eq!(buffer.char_to_column(3), 3);
+        assert_eq!(buffer.char_to_column(4), 0);
+        assert_eq!(buffer.char_to_column(7), 3);
+        assert_eq!(buffer.char_to_column(9), 0);
+        assert_eq!(buffer.char_to_column(14), 5);
+    }
+
+    #[cfg(feature = "graphemes")]
+    #[test]
+    fn graphemes() {
+        // the flag is split across the gap
+        let mut buffer = Buffer::from("🇸b");
+        buffer.insert("a🇺");
+        assert_eq!(buffer.gap_start, "a🇺".len());
+        assert_eq!(buffer.len_graphemes(), 3);
+
+        // pairing of regional indicators depends on the text before the gap
+        let mut buffer = Buffer::from("🇸🇦");
+        buffer.insert("🇺");
+        assert_eq!(buffer.len_graphemes(), 2);
+
+        let mut buffer = Buffer::from("\u{200d}👧 e\u{301}");
+        buffer.insert("👩");
+        assert_eq!(buffer.len_graphemes(), 3);
+        assert_eq!(Buffer::new().len_graphemes(), 0);
+        assert_eq!(Buffer::from("abc").len_graphemes(), 3);
+    }
+
+    #[test]
+    fn display_column() {
+        let mut buffer = Buffer::from("\tab\tc\nxy\t\tz");
+        buffer.move_gap(2);
+        assert_eq!(buffer.char_to_display_column(0, 4), 0);
+        assert_eq!(buffer.char_to_display_column(1, 4), 4);
+        assert_eq!(buffer.char_to_display_column(3, 4), 6);
+        assert_eq!(buffer.char_to_display_column(4, 4), 8);
+        assert_eq!(buffer.char_to_display_column(5, 4), 9);
+        // second line
+        assert_eq!(buffer.char_to_display_column(8, 4), 2);
+        assert_eq!(buffer.char_to_display_column(9, 4), 4);
+        assert_eq!(buffer.char_to_display_column(10, 4), 8);
+        assert_eq!(buffer.char_to_display_column(10, 8), 16);
+        assert_eq!(buffer.char_to_column(10), 4);
+    }
+
+    #[test]
+    fn utf16() {
+        let mut buffer = Buffer::from("😀 world");
+        buffer.insert("hi Θ ");
+        assert_eq!(buffer.to_string(), "hi Θ 😀 world");
+        assert_eq!(buffer.char_to_utf16(5), 5);
+        assert_eq!(buffer.char_to_utf16(6), 7);
+        assert_eq!(buffer.char_to_utf16(12), 13);
+        assert_eq!(buffer.utf16_to_char(5), 5);
+        assert_eq!(buffer.utf16_to_char(6), 5);
+        assert_eq!(buffer.utf16_to_char(7), 6);
+        assert_eq!(buffer.utf16_to_char(13), 12);
+        for pos in 0..=buffer.len_chars() {
+            assert_eq!(buffer.utf16_to_char(buffer.char_to_utf16(pos)), pos);
+        }
+    }
+
+    #[test]
+    fn byte_slice() {
+        let string = "hello Θ world";
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        for (beg, end) in [(0, 14), (2, 5), (3, 8), (6, 8), (7, 14), (6, 6), (14, 14)] {
+            assert!(buffer
+                .byte_slice(beg, end)
+                .eq(string.bytes().skip(beg).take(end - beg)));
+        }
+    }
+
+    #[test]
+    fn find() {
+        let mut buffer = Buffer::from("Θ world wΘrld");
+        buffer.insert("hello ");
+        assert_eq!(buffer.to_string(), "hello Θ world wΘrld");
+        assert_eq!(buffer.find("llo"), Some(2));
+        assert_eq!(buffer.find("o Θ w"), Some(4));
+        assert_eq!(buffer.find("Θ"), Some(6));
+        assert_eq!(buffer.find("rld"), Some(10));
+        assert_eq!(buffer.find("xyz"), None);
+        assert_eq!(buffer.find("hello Θ world wΘrld!"), None);
+        assert_eq!(buffer.find(""), Some(0));
+        assert_eq!(buffer.rfind("rld"), Some(16));
+        assert_eq!(buffer.rfind("Θ"), Some(15));
+        assert_eq!(buffer.rfind(" Θ"), Some(5));
+        assert_eq!(buffer.rfind("llo"), Some(2));
+        assert_eq!(buffer.rfind("xyz"), None);
+        assert_eq!(buffer.rfind(""), Some(19));
+        assert_eq!(Buffer::new().find("a"), None);
+    }
+
+    #[test]
+    fn find_all() {
+        let mut buffer = Buffer::from("bΘ abab Θab");
+        buffer.insert("aΘ a");
+        assert_eq!(buffer.to_string(), "aΘ abΘ abab Θab");
+        assert_eq!(buffer.find_all("ab").collect::<Vec<_>>(), [3, 7, 9, 13]);
+        assert_eq!(buffer.find_all("Θ").collect::<Vec<_>>(), [1, 5, 12]);
+        assert_eq!(buffer.find_all("aba").collect::<Vec<_>>(), [7]);
+        assert_eq!(buffer.find_all("x").count(), 0);
+        assert_eq!(buffer.find_all("").count(), 0);
+    }
+
     #[test]
     fn insert() {
         let string = "hello buffer";
@@ -511,6 +2879,33 @@ mod test {
         assert_eq!(buffer.to_string(), "xhello buffer");
     }
 
+    #[test]
+    fn insert_char_at() {
+        let mut buffer = Buffer::from("hello Θ");
+        assert_eq!(buffer.len_lines(), 1);
+        let edit = buffer.insert_char_at(5, '\n');
+        assert_eq!(
+            edit,
+            Edit {
+                start: 5,
+                old_end: 5,
+                new_end: 6
+            }
+        );
+        assert_eq!(buffer.len_lines(), 2);
+        assert_eq!(buffer.cursor.char, 6);
+        buffer.insert_char_at(8, 'ƽ');
+        buffer.insert_char_at(0, '>');
+        assert_eq!(buffer.to_string(), ">hello\n Θƽ");
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn insert_char_at_out_of_bounds() {
+        Buffer::from("ab").insert_char_at(3, 'x');
+    }
+
     #[test]
     fn insert_slice() {
         let string = "world";
@@ -526,6 +2921,49 @@ mod test {
         assert_eq!(buffer.to_string(), "hi starting Θ text woxrld");
     }
 
+    #[test]
+    fn test_insert_str() {
+        let mut buffer = Buffer::from("world");
+        buffer.insert_str(0, "hello ");
+        assert_eq!(buffer.to_string(), "hello world");
+        buffer.insert_str(11, "!");
+        assert_eq!(buffer.to_string(), "hello world!");
+        buffer.insert_str(5, " Θ");
+        assert_eq!(buffer.to_string(), "hello Θ world!");
+        assert_eq!(buffer.len_chars(), 14);
+    }
+
+    #[test]
+    fn insert_many() {
+        let slices: Vec<_> = ["a", "Θ", "\n", "bc"]
+            .into_iter()
+            .cycle()
+            .take(1000)
+            .collect();
+        let mut buffer = Buffer::from("hello world");
+        buffer.insert_many(6, &slices);
+        // the gap was grown once, with room left for the next insert
+        assert_eq!(buffer.gap_len(), Buffer::GAP_SIZE);
+        let mut expected = Buffer::from("hello world");
+        let mut pos = 6;
+        for slice in &slices {
+            expected.insert_str(pos, slice);
+            pos += slice.chars().count();
+        }
+        assert_eq!(buffer, expected);
+        assert_eq!(buffer.len_chars(), expected.len_chars());
+        assert_eq!(buffer.len_lines(), expected.len_lines());
+        assert_eq!(buffer.cursor.char, pos);
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_insert_str_out_of_bounds() {
+        let mut buffer = Buffer::from("world");
+        buffer.insert_str(6, "x");
+    }
+
     #[test]
     fn empty() {
         let mut buffer = Buffer::from("");
@@ -534,6 +2972,15 @@ mod test {
         assert_eq!(buffer.to_string(), "");
     }
 
+    #[test]
+    fn empty_lengths() {
+        let buffer = Buffer::from("");
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.len_chars(), 0);
+        assert_eq!(buffer.len_lines(), 1);
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
     #[test]
     fn delete() {
         let world = "world";
@@ -573,6 +3020,73 @@ mod test {
         assert_eq!(buffer.to_string(), "hlo rld");
     }
 
+    #[test]
+    fn test_delete_region_len() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        // spans the gap
+        let (bytes, chars) = (buffer.len(), buffer.len_chars());
+        buffer.delete_region(4, 8);
+        assert_eq!(buffer.to_string(), "hellworld");
+        assert_eq!(buffer.len(), bytes - 5);
+        assert_eq!(buffer.len_chars(), chars - 4);
+        // entirely after the gap
+        buffer.delete_region(5, 7);
+        assert_eq!(buffer.to_string(), "hellwld");
+        assert_eq!(buffer.len(), bytes - 7);
+        assert_eq!(buffer.len_chars(), chars - 6);
+    }
+
+    #[test]
+    fn edits() {
+        let edit = |start, old_end, new_end| Edit {
+            start,
+            old_end,
+            new_end,
+        };
+        let mut buffer = Buffer::from("Θ world");
+        assert_eq!(buffer.insert_str(0, "hello "), edit(0, 0, 6));
+        assert_eq!(buffer.insert_str(13, "!"), edit(14, 14, 15));
+        // delete across the gap
+        buffer.move_gap(4);
+        assert_eq!(buffer.delete_region(7, 3), edit(3, 8, 3));
+        assert_eq!(buffer.to_string(), "hel world!");
+        assert_eq!(buffer.replace_range(4, 9, "Ι ƽ"), edit(4, 9, 9));
+        assert_eq!(buffer.to_string(), "hel Ι ƽ!");
+        assert_eq!(buffer.delete_region(20, 30), edit(10, 10, 10));
+        assert_eq!(buffer.insert_at_byte(4, "-"), edit(4, 4, 5));
+        assert_eq!(buffer.to_string(), "hel -Ι ƽ!");
+        assert_eq!(buffer.push_str("ab"), edit(11, 11, 13));
+        assert_eq!(buffer.insert_many(0, &["x", "Θ"]), edit(0, 0, 3));
+        assert_eq!(buffer.insert_buffer(3, &Buffer::from("yz")), edit(4, 4, 6));
+        assert_eq!(buffer.to_string(), "xΘhyzel -Ι ƽ!ab");
+        assert_eq!(buffer.truncate(13), edit(16, 18, 16));
+        assert_eq!(buffer.truncate(20), edit(16, 16, 16));
+        assert_eq!(buffer.append(Buffer::from("!!")), edit(16, 16, 18));
+        assert_eq!(buffer.trim_end_matches('!'), edit(15, 18, 15));
+        assert_eq!(buffer.trim_start_matches('x'), edit(0, 1, 0));
+        assert_eq!(buffer.to_string(), "Θhyzel -Ι ƽ");
+        assert_eq!(Buffer::new().append(Buffer::from("Θa")), edit(0, 0, 3));
+        assert_eq!(Buffer::new().push_str(""), edit(0, 0, 0));
+    }
+
+    #[test]
+    fn replace_range() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        buffer.replace_range(4, 8, "ooo, big ");
+        assert_eq!(buffer.to_string(), "hellooo, big world");
+        assert_eq!(buffer.gap_start, 13);
+        buffer.replace_range(13, 18, "Θ");
+        assert_eq!(buffer.to_string(), "hellooo, big Θ");
+        buffer.replace_range(0, 0, "oh ");
+        assert_eq!(buffer.to_string(), "oh hellooo, big Θ");
+        buffer.replace_range(2, 16, "");
+        assert_eq!(buffer.to_string(), "ohΘ");
+        assert_eq!(buffer.len_chars(), 3);
+        assert_eq!(buffer.validate(), Ok(()));
+    }
+
     #[test]
     fn test_delete_nothing() {
         let mut buffer = Buffer::from("world");
@@ -658,4 +3172,85 @@ mod test {
         buffer.insert(new_string);
         assert_eq!(buffer.gap_chars, new_string.len());
     }
+
+    // random edits checked against a String
+    mod oracle {
+        use super::Buffer;
+        use proptest::prelude::*;
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            Insert(usize, String),
+            Delete(usize, usize),
+            Replace(usize, usize, String),
+            MoveGap(usize),
+            Push(usize, String),
+        }
+
+        fn text() -> impl Strategy<Value = String> {
+            "[a-cΘƽ\n]{0,12}"
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            prop_oneof![
+                (any::<usize>(), text()).prop_map(|(pos, text)| Op::Insert(pos, text)),
+                (any::<usize>(), any::<usize>()).prop_map(|(beg, end)| Op::Delete(beg, end)),
+                (any::<usize>(), any::<usize>(), text())
+                    .prop_map(|(beg, end, text)| Op::Replace(beg, end, text)),
+                any::<usize>().prop_map(Op::MoveGap),
+                (any::<usize>(), text()).prop_map(|(cursor, text)| Op::Push(cursor, text)),
+            ]
+        }
+
+        fn byte_pos(string: &str, pos: usize) -> usize {
+            string
+                .char_indices()
+                .nth(pos)
+                .map_or(string.len(), |(i, _)| i)
+        }
+
+        fn range(len: usize, beg: usize, end: usize) -> (usize, usize) {
+            let (beg, end) = (beg % (len + 1), end % (len + 1));
+            (beg.min(end), beg.max(end))
+        }
+
+        proptest! {
+            #[test]
+            fn edits_match_string(init in text(), ops in prop::collection::vec(op(), 0..32)) {
+                let mut buffer = Buffer::from(init.as_str());
+                let mut string = init;
+                for op in ops {
+                    let len = buffer.len_chars();
+                    match op {
+                        Op::Insert(pos, text) => {
+                            let pos = pos % (len + 1);
+                            buffer.insert_str(pos, &text);
+                            string.insert_str(byte_pos(&string, pos), &text);
+                        }
+                        Op::Delete(beg, end) => {
+                            let (beg, end) = range(len, beg, end);
+                            buffer.delete_region(beg, end);
+                            string.replace_range(byte_pos(&string, beg)..byte_pos(&string, end), "");
+                        }
+                        Op::Replace(beg, end, text) => {
+                            let (beg, end) = range(len, beg, end);
+                            buffer.replace_range(beg, end, &text);
+                            string.replace_range(byte_pos(&string, beg)..byte_pos(&string, end), &text);
+                        }
+                        Op::MoveGap(pos) => buffer.move_gap(pos % (len + 1)),
+                        Op::Push(cursor, text) => {
+                            let cursor = cursor % (len + 1);
+                            buffer.set_cursor(cursor);
+                            buffer.push_str(&text);
+                            string.push_str(&text);
+                            prop_assert_eq!(buffer.cursor.char, cursor);
+                        }
+                    }
+                    prop_assert_eq!(buffer.to_string(), string.as_str());
+                    prop_assert_eq!(buffer.len_chars(), string.chars().count());
+                    prop_assert_eq!(buffer.validate(), Ok(()));
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,187 @@
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::uninlined_format_args)]
+#![allow(clippy::must_use_candidate)]
+use crate::Buffer;
+
+/// A position in a [`Buffer`] that can be moved forwards and backwards one
+/// char at a time without searching from the start of the buffer.
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    /// The text before the gap
+    before: &'a str,
+    /// The text after the gap
+    after: &'a str,
+    /// The current byte offset
+    byte: usize,
+    /// The current char position
+    char: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Create a cursor at the char position `pos` in `buffer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn new(buffer: &'a Buffer, pos: usize) -> Self {
+        let (before, after) = buffer.as_slices();
+        Self {
+            before,
+            after,
+            byte: buffer.char_to_byte(pos),
+            char: pos,
+        }
+    }
+
+    /// Returns the char after the cursor and moves the cursor past it.
+    pub fn next_char(&mut self) -> Option<char> {
+        let chr = self.text_after().chars().next()?;
+        self.byte += chr.len_utf8();
+        self.char += 1;
+        Some(chr)
+    }
+
+    /// Returns the char before the cursor and moves the cursor before it.
+    pub fn prev_char(&mut self) -> Option<char> {
+        let chr = self.text_before().chars().next_back()?;
+        self.byte -= chr.len_utf8();
+        self.char -= 1;
+        Some(chr)
+    }
+
+    /// Move the cursor to the char position `pos`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the end of the buffer.
+    pub fn seek(&mut self, pos: usize) {
+        while self.char < pos {
+            assert!(
+                self.next_char().is_some(),
+                "seek position ({pos}) is out of bounds"
+            );
+        }
+        while self.char > pos {
+            self.prev_char();
+        }
+    }
+
+    /// Returns the text of the line the cursor is on, without its line
+    /// ending.
+    pub fn current_line(&self) -> String {
+        let mut cursor = self.clone();
+        while let Some(chr) = cursor.prev_char() {
+            if chr == '\n' {
+                cursor.next_char();
+                break;
+            }
+        }
+        let mut line = String::new();
+        while let Some(chr) = cursor.next_char() {
+            if chr == '\n' {
+                // only a \r before a \n is part of the line ending
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+                break;
+            }
+            line.push(chr);
+        }
+        line
+    }
+
+    /// The byte offset of the cursor.
+    pub const fn byte_offset(&self) -> usize {
+        self.byte
+    }
+
+    /// The char position of the cursor.
+    pub const fn char_offset(&self) -> usize {
+        self.char
+    }
+
+    /// The text from the cursor to the end of its side of the gap.
+    fn text_after(&self) -> &'a str {
+        match self.byte.checked_sub(self.before.len()) {
+            Some(offset) => &self.after[offset..],
+            None => &self.before[self.byte..],
+        }
+    }
+
+    /// The text from the start of its side of the gap to the cursor.
+    fn text_before(&self) -> &'a str {
+        match self.byte.checked_sub(self.before.len()) {
+            Some(0) | None => &self.before[..self.byte],
+            Some(offset) => &self.after[..offset],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn walk() {
+        let mut buffer = Buffer::from("Θ world ƽ");
+        buffer.insert("hello ");
+        let mut cursor = Cursor::new(&buffer, 0);
+        let forward: Vec<_> = std::iter::from_fn(|| cursor.next_char()).collect();
+        assert_eq!(forward, buffer.chars().collect::<Vec<_>>());
+        assert_eq!(cursor.char_offset(), buffer.len_chars());
+        assert_eq!(cursor.byte_offset(), buffer.len());
+        assert_eq!(cursor.next_char(), None);
+
+        let mut backward: Vec<_> = std::iter::from_fn(|| cursor.prev_char()).collect();
+        backward.reverse();
+        assert_eq!(backward, forward);
+        assert_eq!(cursor.byte_offset(), 0);
+        assert_eq!(cursor.prev_char(), None);
+    }
+
+    #[test]
+    fn seek() {
+        let mut buffer = Buffer::from("Θ world");
+        buffer.insert("hello ");
+        let mut cursor = Cursor::new(&buffer, 7);
+        assert_eq!(cursor.byte_offset(), 8);
+        assert_eq!(cursor.prev_char(), Some('Θ'));
+        cursor.seek(2);
+        assert_eq!(cursor.byte_offset(), 2);
+        assert_eq!(cursor.next_char(), Some('l'));
+        cursor.seek(13);
+        assert_eq!(cursor.byte_offset(), 14);
+        assert_eq!(cursor.prev_char(), Some('d'));
+    }
+
+    #[test]
+    fn current_line() {
+        let mut buffer = Buffer::from("two\r\nthree");
+        buffer.insert("one\nΘ ");
+        let mut cursor = Cursor::new(&buffer, 0);
+        assert_eq!(cursor.current_line(), "one");
+        cursor.seek(3);
+        assert_eq!(cursor.current_line(), "one");
+        cursor.seek(4);
+        assert_eq!(cursor.current_line(), "Θ two");
+        cursor.seek(7);
+        assert_eq!(cursor.current_line(), "Θ two");
+        cursor.seek(9);
+        assert_eq!(cursor.current_line(), "Θ two");
+        cursor.seek(11);
+        assert_eq!(cursor.current_line(), "three");
+        cursor.seek(16);
+        assert_eq!(cursor.current_line(), "three");
+        assert_eq!(Cursor::new(&Buffer::new(), 0).current_line(), "");
+        // a lone \r at the end is not a line ending
+        let buffer = Buffer::from("a\nb\r");
+        assert_eq!(Cursor::new(&buffer, 3).current_line(), "b\r");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn seek_out_of_bounds() {
+        let buffer = Buffer::from("hello");
+        Cursor::new(&buffer, 0).seek(6);
+    }
+}
@@ -1,3 +1,5 @@
 mod buffer;
+mod cursor;
 
 pub use buffer::*;
+pub use cursor::*;